@@ -1,21 +1,69 @@
-use crate::core::{IdGetter, LabelsGetter, NameGetter};
+use crate::core::{DescGetter, IdGetter, LabelsGetter, NameGetter};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+// Marks an entry of cmds as a note, shown with the bookmark but never run.
+pub(crate) const NOTE_PREFIX: &str = "note::";
 
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
 pub(crate) struct Bookmark {
-    #[serde(default)]
+    #[serde(skip)]
     pub id: usize,
+    // Set in the config to keep the id when the bookmarks are reordered.
+    #[serde(default, rename = "id", skip_serializing_if = "Option::is_none")]
+    pub fixed_id: Option<usize>,
+    // Position in the loaded list, which fixed ids don't follow.
+    #[serde(skip)]
+    pub index: usize,
     pub name: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub desc: String,
     pub cmds: Vec<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub labels: Vec<String>,
-    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    // Shown before the name in the list, e.g. an emoji or a nerd font glyph.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub vars: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompt_vars: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub exec: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub separator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_in: Option<RunTarget>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<char>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub confirm: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    // The config file the bookmark was read from, relative to the cwd.
+    #[serde(skip)]
+    pub source: Option<String>,
+}
+
+// Where the generated command is sent when the bookmark is run.
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RunTarget {
+    #[default]
+    Current,
+    Floating,
+    NewTab,
+    NewPane,
 }
 
 impl IdGetter for Bookmark {
@@ -36,10 +84,189 @@ impl LabelsGetter for Bookmark {
     }
 }
 
+impl DescGetter for Bookmark {
+    fn get_desc(&self) -> String {
+        self.desc.clone()
+    }
+}
+
 impl Bookmark {
     pub(crate) fn add_managed_label(&mut self, label: String) {
         if !self.labels.contains(&label) {
             self.labels.push(label);
         }
     }
+
+    // The first command as written in the config, with the number of the
+    // others, e.g. `make +2`. Notes are left out.
+    pub(crate) fn cmd_summary(&self) -> String {
+        let mut cmds = self.cmds.iter().filter(|cmd| !cmd.starts_with(NOTE_PREFIX));
+        let Some(first) = cmds.next() else {
+            return String::new();
+        };
+        let first = first.lines().next().unwrap_or_default();
+        match cmds.count() {
+            0 => first.to_string(),
+            rest => format!("{} +{}", first, rest),
+        }
+    }
+
+    // Description and metadata for the details popup, one entry per line.
+    // Empty fields are left out.
+    pub(crate) fn details(&self) -> Vec<String> {
+        let mut details = Vec::new();
+        if self.desc.trim().is_empty() {
+            details.push("(no description)".to_string());
+        } else {
+            details.extend(self.desc.lines().map(str::to_string));
+        }
+        details.push(String::new());
+        if let Some(group) = self.group.as_ref() {
+            details.push(format!("Group: {}", group));
+        }
+        if !self.labels.is_empty() {
+            details.push(format!("Labels: {}", self.labels.join(", ")));
+        }
+        if let Some(cwd) = self.cwd.as_ref() {
+            details.push(format!("Cwd: {}", cwd));
+        }
+        if let Some(source) = self.source.as_ref() {
+            details.push(format!("Source: {}", source));
+        }
+        let (notes, cmds): (Vec<&String>, Vec<&String>) = self
+            .cmds
+            .iter()
+            .partition(|cmd| cmd.starts_with(NOTE_PREFIX));
+        details.push("Commands:".to_string());
+        details.extend(cmds.iter().map(|cmd| format!("  {}", cmd)));
+        if !notes.is_empty() {
+            details.push("Notes:".to_string());
+            details.extend(
+                notes
+                    .iter()
+                    .map(|note| format!("  {}", note[NOTE_PREFIX.len()..].trim())),
+            );
+        }
+        if !self.vars.is_empty() {
+            details.push("Vars:".to_string());
+            let vars = self.vars.iter().collect::<BTreeMap<_, _>>();
+            details.extend(
+                vars.iter()
+                    .map(|(name, value)| format!("  {} = {}", name, value)),
+            );
+        }
+        details
+    }
+
+    // Hash of the user-defined content, used to detect changes between reloads.
+    // The assigned id is left out since it depends on the position in the list.
+    pub(crate) fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.fixed_id.hash(&mut hasher);
+        self.name.hash(&mut hasher);
+        self.desc.hash(&mut hasher);
+        self.cmds.hash(&mut hasher);
+        self.labels.hash(&mut hasher);
+        self.group.hash(&mut hasher);
+        self.icon.hash(&mut hasher);
+        self.vars
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .hash(&mut hasher);
+        self.prompt_vars.hash(&mut hasher);
+        self.exec.hash(&mut hasher);
+        self.separator.hash(&mut hasher);
+        self.run_in.hash(&mut hasher);
+        self.shell.hash(&mut hasher);
+        self.cwd.hash(&mut hasher);
+        self.env
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .hash(&mut hasher);
+        self.key.hash(&mut hasher);
+        self.confirm.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bookmark, RunTarget};
+
+    #[test]
+    fn run_in_is_deserialized_from_snake_case() {
+        let bookmark: Bookmark =
+            serde_yaml::from_str("name: deploy\ncmds: [make deploy]\nrun_in: new_tab\n").unwrap();
+
+        assert_eq!(bookmark.run_in, Some(RunTarget::NewTab));
+    }
+
+    #[test]
+    fn run_in_is_optional() {
+        let bookmark: Bookmark =
+            serde_yaml::from_str("name: deploy\ncmds: [make deploy]\n").unwrap();
+
+        assert_eq!(bookmark.run_in, None);
+    }
+
+    #[test]
+    fn confirm_defaults_to_false_and_is_not_serialized_then() {
+        let bookmark: Bookmark =
+            serde_yaml::from_str("name: deploy\ncmds: [make deploy]\n").unwrap();
+        assert!(!bookmark.confirm);
+        assert!(!serde_yaml::to_string(&bookmark)
+            .unwrap()
+            .contains("confirm"));
+
+        let bookmark: Bookmark =
+            serde_yaml::from_str("name: wipe\ncmds: [rm -rf data]\nconfirm: true\n").unwrap();
+        assert!(bookmark.confirm);
+    }
+
+    #[test]
+    fn cmd_summary_counts_the_other_commands() {
+        let bookmark = |cmds: &[&str]| Bookmark {
+            cmds: cmds.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(bookmark(&[]).cmd_summary(), "");
+        assert_eq!(bookmark(&["make"]).cmd_summary(), "make");
+        assert_eq!(
+            bookmark(&["note:: slow", "cmd::build", "make test", "make lint"]).cmd_summary(),
+            "cmd::build +2"
+        );
+    }
+
+    #[test]
+    fn details_list_the_description_and_metadata() {
+        let bookmark: Bookmark = serde_yaml::from_str(
+            "name: deploy\ndesc: Deploys the app\ncmds: [make deploy, 'note:: needs VPN']\nlabels: [prod]\nvars: {b: '2', a: '1'}\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            bookmark.details(),
+            vec![
+                "Deploys the app",
+                "",
+                "Labels: prod",
+                "Commands:",
+                "  make deploy",
+                "Notes:",
+                "  needs VPN",
+                "Vars:",
+                "  a = 1",
+                "  b = 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn pinned_defaults_to_false_and_is_not_serialized_then() {
+        let bookmark: Bookmark =
+            serde_yaml::from_str("name: deploy\ncmds: [make deploy]\n").unwrap();
+        assert!(!bookmark.pinned);
+        assert!(!serde_yaml::to_string(&bookmark).unwrap().contains("pinned"));
+    }
 }
@@ -1,15 +1,31 @@
 use super::bookmark::Bookmark;
+use super::label::MANAGED_LABEL_PREFIX;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fmt::Formatter;
+use std::path::Path;
 
 pub type BookmarkList = Vec<Bookmark>;
 
 #[derive(Default, Debug, Serialize, Deserialize, Clone)]
 pub(crate) struct Config {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub includes: Vec<String>,
     #[serde(default)]
     pub vars: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub var_defaults: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_vars: Vec<String>,
     #[serde(default)]
     pub cmds: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub workflows: HashMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub label_colors: HashMap<String, usize>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub icons: HashMap<String, String>,
     #[serde(deserialize_with = "deserialize_bookmarks")]
     pub bookmarks: BookmarkList,
 }
@@ -22,12 +38,36 @@ impl Config {
             }
         }
 
+        for key in other.var_defaults.keys() {
+            if self.var_defaults.contains_key(key) {
+                return Err(format!("Duplicate var default: {}", key));
+            }
+        }
+
         for key in other.cmds.keys() {
             if self.cmds.contains_key(key) {
                 return Err(format!("Duplicate cmd name: {}", key));
             }
         }
 
+        for key in other.workflows.keys() {
+            if self.workflows.contains_key(key) {
+                return Err(format!("Duplicate workflow name: {}", key));
+            }
+        }
+
+        for key in other.label_colors.keys() {
+            if self.label_colors.contains_key(key) {
+                return Err(format!("Duplicate label color: {}", key));
+            }
+        }
+
+        for key in other.icons.keys() {
+            if self.icons.contains_key(key) {
+                return Err(format!("Duplicate icon: {}", key));
+            }
+        }
+
         let mut bookmark_names: HashSet<String> = self
             .bookmarks
             .iter()
@@ -40,16 +80,321 @@ impl Config {
         }
 
         self.vars.extend(other.vars);
+        self.var_defaults.extend(other.var_defaults);
+        for var in other.required_vars {
+            if !self.required_vars.contains(&var) {
+                self.required_vars.push(var);
+            }
+        }
         self.cmds.extend(other.cmds);
+        self.workflows.extend(other.workflows);
+        self.label_colors.extend(other.label_colors);
+        self.icons.extend(other.icons);
         self.bookmarks.extend(other.bookmarks);
         self.reindex_bookmarks();
 
         Ok(())
     }
 
+    // Unlike merge, entries of the other config replace same-named ones.
+    pub(crate) fn override_with(&mut self, other: Config) {
+        self.vars.extend(other.vars);
+        self.var_defaults.extend(other.var_defaults);
+        for var in other.required_vars {
+            if !self.required_vars.contains(&var) {
+                self.required_vars.push(var);
+            }
+        }
+        self.cmds.extend(other.cmds);
+        self.workflows.extend(other.workflows);
+        self.label_colors.extend(other.label_colors);
+        self.icons.extend(other.icons);
+        for bookmark in other.bookmarks {
+            match self.bookmarks.iter_mut().find(|b| b.name == bookmark.name) {
+                Some(existing) => *existing = bookmark,
+                None => self.bookmarks.push(bookmark),
+            }
+        }
+        self.reindex_bookmarks();
+    }
+
+    // A config with just the named bookmarks, the bookmarks they depend on and
+    // the cmds and vars they reference. Managed file labels are dropped.
+    pub(crate) fn export(&self, names: &[String]) -> Config {
+        let mut selected: Vec<String> = names.to_vec();
+        let mut i = 0;
+        while i < selected.len() {
+            if let Some(bookmark) = self.bookmarks.iter().find(|b| b.name == selected[i]) {
+                for dep in bookmark
+                    .cmds
+                    .iter()
+                    .filter_map(|c| c.strip_prefix("bookmark::"))
+                {
+                    if !selected.iter().any(|name| name == dep) {
+                        selected.push(dep.to_string());
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        let bookmarks: BookmarkList = self
+            .bookmarks
+            .iter()
+            .filter(|b| selected.contains(&b.name))
+            .cloned()
+            .map(|mut b| {
+                b.labels
+                    .retain(|label| !label.starts_with(MANAGED_LABEL_PREFIX));
+                b
+            })
+            .collect();
+
+        let cmds: HashMap<String, String> = bookmarks
+            .iter()
+            .flat_map(|b| b.cmds.iter().filter_map(|c| c.strip_prefix("cmd::")))
+            .filter_map(|key| self.cmds.get_key_value(key))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        // Vars can reference each other, so the search repeats until no new var is found.
+        let mut texts: Vec<&String> = bookmarks
+            .iter()
+            .flat_map(|b| b.cmds.iter().chain(b.vars.values()))
+            .chain(cmds.values())
+            .collect();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        loop {
+            let tokens = template_tokens(&texts);
+            let found: Vec<(&String, &String)> = self
+                .vars
+                .iter()
+                .filter(|(name, _)| tokens.contains(name.as_str()) && !vars.contains_key(*name))
+                .collect();
+            if found.is_empty() {
+                break;
+            }
+            for (name, value) in found {
+                vars.insert(name.clone(), value.clone());
+                texts.push(value);
+            }
+        }
+        let tokens = template_tokens(&texts);
+
+        let labels: HashSet<&String> = bookmarks.iter().flat_map(|b| b.labels.iter()).collect();
+
+        Config {
+            includes: Vec::new(),
+            vars,
+            var_defaults: self
+                .var_defaults
+                .iter()
+                .filter(|(name, _)| tokens.contains(name.as_str()))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            required_vars: self
+                .required_vars
+                .iter()
+                .filter(|name| tokens.contains(name.as_str()))
+                .cloned()
+                .collect(),
+            cmds,
+            workflows: HashMap::new(),
+            label_colors: self
+                .label_colors
+                .iter()
+                .filter(|(label, _)| labels.contains(label))
+                .map(|(k, v)| (k.clone(), *v))
+                .collect(),
+            icons: self
+                .icons
+                .iter()
+                .filter(|(name, _)| {
+                    labels.contains(name)
+                        || bookmarks.iter().any(|b| b.group.as_ref() == Some(name))
+                })
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            bookmarks,
+        }
+    }
+
+    // Adds the bookmarks, cmds and vars whose names are not taken yet and
+    // returns the names of the skipped bookmarks.
+    pub(crate) fn import(&mut self, other: Config, taken: &HashSet<String>) -> Vec<String> {
+        let mut skipped = Vec::new();
+        for bookmark in other.bookmarks {
+            if taken.contains(&bookmark.name)
+                || self.bookmarks.iter().any(|b| b.name == bookmark.name)
+            {
+                skipped.push(bookmark.name);
+            } else {
+                self.bookmarks.push(bookmark);
+            }
+        }
+        for (key, value) in other.cmds {
+            self.cmds.entry(key).or_insert(value);
+        }
+        for (key, value) in other.vars {
+            self.vars.entry(key).or_insert(value);
+        }
+        for (key, value) in other.var_defaults {
+            self.var_defaults.entry(key).or_insert(value);
+        }
+        for var in other.required_vars {
+            if !self.required_vars.contains(&var) {
+                self.required_vars.push(var);
+            }
+        }
+        for (key, value) in other.label_colors {
+            self.label_colors.entry(key).or_insert(value);
+        }
+        for (key, value) in other.icons {
+            self.icons.entry(key).or_insert(value);
+        }
+        self.reindex_bookmarks();
+
+        skipped
+    }
+
+    // The bookmark's own icon, or the one configured for its group or else
+    // for its first label.
+    pub(crate) fn icon<'a>(&'a self, bookmark: &'a Bookmark) -> Option<&'a str> {
+        bookmark
+            .icon
+            .as_ref()
+            .or_else(|| bookmark.group.as_ref().and_then(|g| self.icons.get(g)))
+            .or_else(|| bookmark.labels.first().and_then(|l| self.icons.get(l)))
+            .map(String::as_str)
+    }
+
+    // Duplicate ids are reported once the whole config is loaded.
     fn reindex_bookmarks(&mut self) {
-        for (i, bookmark) in self.bookmarks.iter_mut().enumerate() {
-            bookmark.id = i + 1;
+        let _ = assign_ids(&mut self.bookmarks);
+    }
+}
+
+// Bookmarks keep the id set in the config. The others are numbered in list
+// order, skipping the ids that are taken.
+pub(crate) fn assign_ids(bookmarks: &mut [Bookmark]) -> Result<(), String> {
+    let mut taken = HashSet::new();
+    let mut duplicates = Vec::new();
+    for id in bookmarks.iter().filter_map(|bookmark| bookmark.fixed_id) {
+        if !taken.insert(id) && !duplicates.contains(&id) {
+            duplicates.push(id);
+        }
+    }
+
+    let mut next = 1;
+    for bookmark in bookmarks.iter_mut() {
+        bookmark.id = match bookmark.fixed_id {
+            Some(id) => id,
+            None => {
+                while taken.contains(&next) {
+                    next += 1;
+                }
+                next += 1;
+                next - 1
+            }
+        };
+    }
+
+    if !duplicates.is_empty() {
+        let ids: Vec<String> = duplicates.iter().map(usize::to_string).collect();
+        return Err(format!("Duplicate bookmark ids: {}", ids.join(", ")));
+    }
+    Ok(())
+}
+
+// Identifiers used inside `{{ ... }}` blocks of the templates.
+fn template_tokens<'a>(texts: &[&'a String]) -> HashSet<&'a str> {
+    let mut tokens = HashSet::new();
+    for text in texts {
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find("{{") {
+            rest = &rest[start + 2..];
+            let end = rest.find("}}").unwrap_or(rest.len());
+            tokens.extend(
+                rest[..end]
+                    .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .filter(|token| !token.is_empty()),
+            );
+            rest = &rest[end..];
+        }
+    }
+    tokens
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ConfigFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Yaml => "YAML",
+            Self::Toml => "TOML",
+            Self::Json => "JSON",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl ConfigFormat {
+    // Unrecognized extensions fall back to YAML.
+    pub(crate) fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::Toml,
+            Some("json") => Self::Json,
+            _ => Self::Yaml,
+        }
+    }
+
+    pub(crate) fn is_config_file(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| matches!(ext, "yaml" | "yml" | "toml" | "json"))
+            .unwrap_or(false)
+    }
+
+    // YAML and JSON errors point at the offending line, so it is quoted. TOML
+    // errors already include it.
+    pub(crate) fn parse(&self, content: &str) -> Result<Config, Box<dyn std::error::Error>> {
+        let result: Result<Config, (Box<dyn std::error::Error>, Option<usize>)> = match self {
+            Self::Yaml => serde_yaml::from_str(content).map_err(|e| {
+                let line = e.location().map(|location| location.line());
+                (e.into(), line)
+            }),
+            Self::Toml => toml::from_str(content).map_err(|e| (e.into(), None)),
+            Self::Json => serde_json::from_str(content).map_err(|e| {
+                let line = Some(e.line()).filter(|line| *line > 0);
+                (e.into(), line)
+            }),
+        };
+        result.map_err(|(e, line)| {
+            let snippet = line
+                .and_then(|line| {
+                    content
+                        .lines()
+                        .nth(line - 1)
+                        .map(|text| (line, text.trim()))
+                })
+                .filter(|(_, text)| !text.is_empty())
+                .map_or(String::new(), |(line, text)| {
+                    format!(", near line {}: `{}`", line, text)
+                });
+            format!("Invalid {} config: {}{}", self, e, snippet).into()
+        })
+    }
+
+    pub(crate) fn serialize(&self, config: &Config) -> Result<String, Box<dyn std::error::Error>> {
+        match self {
+            Self::Yaml => Ok(serde_yaml::to_string(config)?),
+            Self::Toml => Ok(toml::to_string(config)?),
+            Self::Json => Ok(serde_json::to_string_pretty(config)?),
         }
     }
 }
@@ -63,32 +408,36 @@ where
     let bookmarks: Vec<Bookmark> = Vec::deserialize(deserializer)?;
     let mut result: Vec<Bookmark> = Vec::new();
     let mut uniq: HashSet<String> = HashSet::new();
-    let mut duplicate_count = 0;
+    let mut duplicates: Vec<String> = Vec::new();
 
-    for (i, mut bookmark) in bookmarks.into_iter().enumerate() {
+    for bookmark in bookmarks {
         if !uniq.insert(bookmark.name.clone()) {
-            duplicate_count += 1;
+            if !duplicates.contains(&bookmark.name) {
+                duplicates.push(bookmark.name);
+            }
         } else {
-            bookmark.id = i + 1;
             result.push(bookmark);
         }
     }
 
-    if duplicate_count > 0 {
+    if !duplicates.is_empty() {
         return Err(serde::de::Error::custom(format!(
-            "Duplicate bookmarks names: {}",
-            duplicate_count
+            "Duplicate bookmark names: {}",
+            duplicates.join(", ")
         )));
     }
 
+    assign_ids(&mut result).map_err(serde::de::Error::custom)?;
+
     Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{BookmarkList, Config};
+    use super::{assign_ids, BookmarkList, Config, ConfigFormat};
     use crate::bookmark::Bookmark;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
+    use std::path::Path;
 
     fn bookmark(name: &str) -> Bookmark {
         Bookmark {
@@ -104,11 +453,13 @@ mod tests {
             vars: HashMap::from([(String::from("base"), String::from("value"))]),
             cmds: HashMap::from([(String::from("hello"), String::from("echo base"))]),
             bookmarks: BookmarkList::from([bookmark("base")]),
+            ..Default::default()
         };
         let extra = Config {
             vars: HashMap::from([(String::from("extra"), String::from("value"))]),
             cmds: HashMap::from([(String::from("world"), String::from("echo extra"))]),
             bookmarks: BookmarkList::from([bookmark("extra")]),
+            ..Default::default()
         };
 
         base.merge(extra).unwrap();
@@ -125,14 +476,12 @@ mod tests {
     #[test]
     fn merge_rejects_duplicate_bookmark_names() {
         let mut base = Config {
-            vars: HashMap::new(),
-            cmds: HashMap::new(),
             bookmarks: BookmarkList::from([bookmark("dup")]),
+            ..Default::default()
         };
         let extra = Config {
-            vars: HashMap::new(),
-            cmds: HashMap::new(),
             bookmarks: BookmarkList::from([bookmark("dup")]),
+            ..Default::default()
         };
 
         let err = base.merge(extra).unwrap_err();
@@ -144,13 +493,11 @@ mod tests {
     fn merge_rejects_duplicate_var_names() {
         let mut base = Config {
             vars: HashMap::from([(String::from("shared"), String::from("base"))]),
-            cmds: HashMap::new(),
-            bookmarks: BookmarkList::new(),
+            ..Default::default()
         };
         let extra = Config {
             vars: HashMap::from([(String::from("shared"), String::from("extra"))]),
-            cmds: HashMap::new(),
-            bookmarks: BookmarkList::new(),
+            ..Default::default()
         };
 
         let err = base.merge(extra).unwrap_err();
@@ -161,18 +508,267 @@ mod tests {
     #[test]
     fn merge_rejects_duplicate_cmd_names() {
         let mut base = Config {
-            vars: HashMap::new(),
             cmds: HashMap::from([(String::from("hello"), String::from("echo base"))]),
-            bookmarks: BookmarkList::new(),
+            ..Default::default()
         };
         let extra = Config {
-            vars: HashMap::new(),
             cmds: HashMap::from([(String::from("hello"), String::from("echo extra"))]),
-            bookmarks: BookmarkList::new(),
+            ..Default::default()
         };
 
         let err = base.merge(extra).unwrap_err();
 
         assert!(err.contains("Duplicate cmd name: hello"));
     }
+
+    #[test]
+    fn merge_rejects_duplicate_workflow_names() {
+        let mut base = Config {
+            workflows: HashMap::from([(String::from("ship"), vec![String::from("build")])]),
+            ..Default::default()
+        };
+        let extra = Config {
+            workflows: HashMap::from([(String::from("ship"), vec![String::from("test")])]),
+            ..Default::default()
+        };
+
+        let err = base.merge(extra).unwrap_err();
+
+        assert!(err.contains("Duplicate workflow name: ship"));
+    }
+
+    #[test]
+    fn override_with_replaces_same_named_entries() {
+        let mut base = Config {
+            vars: HashMap::from([(String::from("path"), String::from("/base"))]),
+            bookmarks: BookmarkList::from([bookmark("shared"), bookmark("base")]),
+            ..Default::default()
+        };
+        let mut shared = bookmark("shared");
+        shared.cmds = vec![String::from("echo override")];
+        let project = Config {
+            vars: HashMap::from([(String::from("path"), String::from("/project"))]),
+            bookmarks: BookmarkList::from([shared, bookmark("project")]),
+            ..Default::default()
+        };
+
+        base.override_with(project);
+
+        assert_eq!(base.vars.get("path"), Some(&String::from("/project")));
+        let names: Vec<&str> = base.bookmarks.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["shared", "base", "project"]);
+        assert_eq!(base.bookmarks[0].cmds, vec![String::from("echo override")]);
+        assert_eq!(base.bookmarks[2].id, 3);
+    }
+
+    #[test]
+    fn config_format_is_detected_from_extension() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a.yml")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a.conf")),
+            ConfigFormat::Yaml
+        );
+    }
+
+    #[test]
+    fn toml_config_round_trips_and_rejects_duplicates() {
+        let default = ConfigFormat::Toml.serialize(&Config::default()).unwrap();
+        assert!(ConfigFormat::Toml
+            .parse(&default)
+            .unwrap()
+            .bookmarks
+            .is_empty());
+
+        let content = r#"
+[vars]
+path = "/tmp"
+
+[[bookmarks]]
+name = "list"
+cmds = ["ls {{ path }}"]
+"#;
+        let config = ConfigFormat::Toml.parse(content).unwrap();
+        assert_eq!(config.bookmarks[0].name, "list");
+        assert_eq!(config.bookmarks[0].id, 1);
+
+        let duplicated = r#"
+[[bookmarks]]
+name = "dup"
+cmds = ["echo 1"]
+
+[[bookmarks]]
+name = "dup"
+cmds = ["echo 2"]
+"#;
+        let err = ConfigFormat::Toml.parse(duplicated).unwrap_err();
+        assert!(err.to_string().contains("Duplicate bookmark names: dup"));
+    }
+
+    #[test]
+    fn json_config_round_trips_and_reports_format_on_errors() {
+        let default = ConfigFormat::Json.serialize(&Config::default()).unwrap();
+        assert!(ConfigFormat::Json
+            .parse(&default)
+            .unwrap()
+            .bookmarks
+            .is_empty());
+
+        let duplicated = r#"{"bookmarks": [
+            {"name": "dup", "cmds": ["echo 1"]},
+            {"name": "dup", "cmds": ["echo 2"]}
+        ]}"#;
+        let err = ConfigFormat::Json
+            .parse(duplicated)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Invalid JSON config:"));
+        assert!(err.contains("Duplicate bookmark names: dup"));
+    }
+
+    #[test]
+    fn export_keeps_selected_bookmarks_with_their_dependencies() {
+        let mut deploy = bookmark("deploy");
+        deploy.cmds = vec![
+            "bookmark::build".to_string(),
+            "kubectl apply -n {{ns}}".to_string(),
+        ];
+        deploy.labels = vec!["k8s".to_string(), "file::main".to_string()];
+        let mut build = bookmark("build");
+        build.cmds = vec!["cmd::compile".to_string()];
+        let config = Config {
+            vars: HashMap::from([
+                (String::from("ns"), String::from("{{team}}-prod")),
+                (String::from("team"), String::from("core")),
+                (String::from("unused"), String::from("value")),
+            ]),
+            cmds: HashMap::from([
+                (String::from("compile"), String::from("make")),
+                (String::from("other"), String::from("ls")),
+            ]),
+            label_colors: HashMap::from([(String::from("k8s"), 1)]),
+            bookmarks: BookmarkList::from([bookmark("other"), build, deploy]),
+            ..Default::default()
+        };
+
+        let exported = config.export(&["deploy".to_string()]);
+
+        let names: Vec<&str> = exported.bookmarks.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["build", "deploy"]);
+        assert_eq!(exported.bookmarks[1].labels, vec!["k8s".to_string()]);
+        assert_eq!(exported.cmds.len(), 1);
+        assert!(exported.cmds.contains_key("compile"));
+        assert_eq!(exported.vars.len(), 2);
+        assert!(exported.vars.contains_key("ns") && exported.vars.contains_key("team"));
+        assert_eq!(exported.label_colors.get("k8s"), Some(&1));
+    }
+
+    #[test]
+    fn icon_prefers_the_bookmark_then_its_group_then_its_first_label() {
+        let config = Config {
+            icons: HashMap::from([
+                (String::from("infra"), String::from("🛠")),
+                (String::from("docker"), String::from("🐳")),
+            ]),
+            ..Default::default()
+        };
+        let mut b = bookmark("build");
+        assert_eq!(config.icon(&b), None);
+
+        b.labels = vec![String::from("docker"), String::from("infra")];
+        assert_eq!(config.icon(&b), Some("🐳"));
+
+        b.group = Some(String::from("infra"));
+        assert_eq!(config.icon(&b), Some("🛠"));
+
+        b.icon = Some(String::from("🚀"));
+        assert_eq!(config.icon(&b), Some("🚀"));
+    }
+
+    #[test]
+    fn import_skips_taken_names_and_keeps_existing_entries() {
+        let mut base = Config {
+            cmds: HashMap::from([(String::from("hello"), String::from("echo base"))]),
+            bookmarks: BookmarkList::from([bookmark("base")]),
+            ..Default::default()
+        };
+        let other = Config {
+            cmds: HashMap::from([
+                (String::from("hello"), String::from("echo other")),
+                (String::from("world"), String::from("echo world")),
+            ]),
+            bookmarks: BookmarkList::from([bookmark("base"), bookmark("other"), bookmark("extra")]),
+            ..Default::default()
+        };
+
+        let skipped = base.import(other, &HashSet::from([String::from("extra")]));
+
+        assert_eq!(skipped, vec!["base".to_string(), "extra".to_string()]);
+        let names: Vec<&str> = base.bookmarks.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["base", "other"]);
+        assert_eq!(base.bookmarks[1].id, 2);
+        assert_eq!(base.cmds.get("hello"), Some(&String::from("echo base")));
+        assert!(base.cmds.contains_key("world"));
+    }
+
+    #[test]
+    fn yaml_parse_errors_quote_the_offending_line() {
+        let content = "bookmarks:\n  - name: build\n    cmds: [make\n  - name: test\n";
+        let err = ConfigFormat::Yaml.parse(content).unwrap_err().to_string();
+        assert!(err.starts_with("Invalid YAML config:"));
+        assert!(err.ends_with(", near line 4: `- name: test`"), "{}", err);
+    }
+
+    #[test]
+    fn duplicate_bookmark_errors_list_each_name_once() {
+        let content = r#"
+bookmarks:
+  - {name: deploy, cmds: [a]}
+  - {name: build, cmds: [b]}
+  - {name: deploy, cmds: [c]}
+  - {name: build, cmds: [d]}
+  - {name: deploy, cmds: [e]}
+"#;
+        let err = ConfigFormat::Yaml.parse(content).unwrap_err().to_string();
+        assert!(
+            err.contains("Duplicate bookmark names: deploy, build"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn assign_ids_keeps_fixed_ids_and_numbers_the_rest_around_them() {
+        let fixed = |name: &str, id| Bookmark {
+            fixed_id: Some(id),
+            ..bookmark(name)
+        };
+        let mut bookmarks = vec![bookmark("a"), fixed("b", 1), bookmark("c"), fixed("d", 7)];
+        assign_ids(&mut bookmarks).unwrap();
+        let ids: Vec<usize> = bookmarks.iter().map(|b| b.id).collect();
+        assert_eq!(ids, vec![2, 1, 3, 7]);
+
+        let content =
+            "bookmarks:\n  - {id: 3, name: a, cmds: [a]}\n  - {id: 3, name: b, cmds: [b]}\n";
+        let err = ConfigFormat::Yaml.parse(content).unwrap_err().to_string();
+        assert!(err.contains("Duplicate bookmark ids: 3"), "{}", err);
+    }
+
+    #[test]
+    fn fixed_ids_are_kept_when_the_config_is_written_back() {
+        let config = ConfigFormat::Yaml
+            .parse("bookmarks:\n  - {id: 7, name: a, cmds: [a]}\n  - {name: b, cmds: [b]}\n")
+            .unwrap();
+        assert_eq!(config.bookmarks[1].id, 1);
+
+        let written = ConfigFormat::Yaml.serialize(&config).unwrap();
+        assert!(written.contains("id: 7"), "{}", written);
+        assert_eq!(written.matches("id:").count(), 1, "{}", written);
+    }
 }
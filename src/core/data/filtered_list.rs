@@ -1,10 +1,13 @@
-use crate::core::filtering::Filter;
+use crate::core::filtering::{Filter, NameGetter};
 
 #[derive(Default)]
 pub struct FilteredList<T> {
     origin: Vec<T>,
     items: Vec<Item<T>>,
     selected: usize,
+    limit: Option<usize>,
+    // Moving past either end jumps to the other one instead of stopping.
+    wrap: bool,
 }
 
 pub struct Item<T> {
@@ -24,12 +27,27 @@ impl<T: Clone> FilteredList<T> {
                 })
                 .collect(),
             selected: 0,
+            limit: None,
+            wrap: true,
         }
     }
 
+    // Caps how many filtered items are shown and selectable at once.
+    pub fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
     pub fn select_down(&mut self) {
-        if self.selected == self.items.len() - 1 {
-            self.selected = 0;
+        if self.selected + 1 >= self.len() {
+            if self.wrap {
+                self.selected = 0;
+            }
             return;
         }
         self.selected += 1;
@@ -37,12 +55,27 @@ impl<T: Clone> FilteredList<T> {
 
     pub fn select_up(&mut self) {
         if self.selected == 0 {
-            self.selected = self.items.len() - 1;
+            if self.wrap {
+                self.selected = self.len().saturating_sub(1);
+            }
             return;
         }
         self.selected -= 1;
     }
 
+    pub fn select_page_down(&mut self, page: usize) {
+        self.selected = (self.selected + page).min(self.len().saturating_sub(1));
+    }
+
+    pub fn select_page_up(&mut self, page: usize) {
+        self.selected = self.selected.saturating_sub(page);
+    }
+
+    // Selects the item at the position, clamped to the end of the list.
+    pub fn select(&mut self, position: usize) {
+        self.selected = position.min(self.len().saturating_sub(1));
+    }
+
     pub fn reset_selection(&mut self) {
         self.selected = 0;
     }
@@ -59,27 +92,227 @@ impl<T: Clone> FilteredList<T> {
     }
 
     pub fn len(&self) -> usize {
+        match self.limit {
+            Some(limit) => self.items.len().min(limit),
+            None => self.items.len(),
+        }
+    }
+
+    // Number of filtered items, including those hidden by the limit.
+    pub fn total_len(&self) -> usize {
         self.items.len()
     }
 
+    // Number of items before filtering.
+    pub fn origin_len(&self) -> usize {
+        self.origin.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Item<T>)> {
+        self.items.iter().take(self.len()).enumerate()
+    }
+
+    // Every filtered item, including those hidden by the limit.
+    pub fn iter_all(&self) -> impl Iterator<Item = &T> {
+        self.items.iter().map(|item| &item.value)
+    }
+}
+
+impl<T: Clone + NameGetter> FilteredList<T> {
+    // Keeps the selected item (matched by name) selected if it survives the filter.
     pub fn with_filter(&mut self, f: Box<dyn Filter<T>>) {
+        let selected_name = self.get_selected().map(|item| item.get_name());
         let mut items = Vec::new();
 
         for item in self.origin.iter() {
-            let (keep, i) = f.keep_indices(item);
+            let (keep, score, i) = f.keep_scored(item);
             if keep {
-                items.push(Item {
-                    value: item.clone(),
-                    indices: i,
-                });
+                items.push((
+                    score,
+                    Item {
+                        value: item.clone(),
+                        indices: i,
+                    },
+                ));
             }
         }
+        // Stable, so equally scored items keep their original order.
+        items.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
 
-        self.items = items;
-        self.reset_selection();
+        self.items = items.into_iter().map(|(_, item)| item).collect();
+        self.select_by_name(selected_name);
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (usize, &Item<T>)> {
-        self.items.iter().enumerate()
+    // Reorders the items, keeping the selected item selected. The sort is
+    // stable and also applies to items that later pass a filter.
+    pub fn sort_by_key<K: Ord>(&mut self, key: impl Fn(&T) -> K) {
+        let selected_name = self.get_selected().map(|item| item.get_name());
+        self.origin.sort_by_key(&key);
+        self.items.sort_by_key(|item| key(&item.value));
+        self.select_by_name(selected_name);
+    }
+
+    pub fn select_named(&mut self, name: &str) {
+        self.select_by_name(Some(name.to_string()));
+    }
+
+    fn select_by_name(&mut self, name: Option<String>) {
+        self.selected = name
+            .and_then(|name| {
+                self.items
+                    .iter()
+                    .take(self.len())
+                    .position(|item| item.value.get_name() == name)
+            })
+            .unwrap_or(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FilteredList;
+    use crate::core::{FilterMode, GenericFilter};
+    use crate::label::Label;
+
+    fn filter(value: &str) -> Box<GenericFilter> {
+        Box::new(GenericFilter::new(
+            FilterMode::Name,
+            value.to_string(),
+            true,
+            false,
+        ))
+    }
+
+    fn list() -> FilteredList<Label> {
+        FilteredList::new(vec![
+            Label::new(1, "alpha".to_string()),
+            Label::new(2, "beta".to_string()),
+            Label::new(3, "gamma".to_string()),
+        ])
+    }
+
+    #[test]
+    fn with_filter_keeps_selected_item_when_it_remains_visible() {
+        let mut list = list();
+        list.select_down();
+        list.select_down();
+
+        list.with_filter(filter("gam"));
+        assert_eq!(list.get_selected().unwrap().name, "gamma");
+
+        list.with_filter(filter(""));
+        assert_eq!(list.get_position(), 2);
+        assert_eq!(list.get_selected().unwrap().name, "gamma");
+    }
+
+    #[test]
+    fn with_filter_resets_selection_when_selected_item_is_filtered_out() {
+        let mut list = list();
+        list.select_down();
+
+        list.with_filter(filter("a"));
+        assert_eq!(list.get_selected().unwrap().name, "beta");
+
+        list.with_filter(filter("gam"));
+        assert_eq!(list.get_position(), 0);
+        assert_eq!(list.get_selected().unwrap().name, "gamma");
+    }
+
+    #[test]
+    fn with_filter_clamps_a_scrolled_selection_to_a_shorter_list() {
+        let mut list = list();
+        list.select(2);
+
+        list.with_filter(filter("zzz"));
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.get_position(), 0);
+        assert!(list.get_selected().is_none());
+        list.select_down();
+        list.select_up();
+        assert_eq!(list.get_position(), 0);
+
+        list.with_filter(filter(""));
+        list.select(2);
+        list.with_filter(filter("alp"));
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.get_position(), 0);
+        assert_eq!(list.get_selected().unwrap().name, "alpha");
+    }
+
+    #[test]
+    fn sort_by_key_keeps_the_selected_item_and_survives_filtering() {
+        let mut list = list();
+        list.select_down();
+
+        list.sort_by_key(|label| std::cmp::Reverse(label.id));
+        assert_eq!(list.get_position(), 1);
+        assert_eq!(list.get_selected().unwrap().name, "beta");
+
+        list.with_filter(filter(""));
+        let names: Vec<String> = list
+            .iter()
+            .map(|(_, item)| item.value.name.clone())
+            .collect();
+        assert_eq!(names, vec!["gamma", "beta", "alpha"]);
+    }
+
+    #[test]
+    fn page_navigation_is_clamped_to_the_list_bounds() {
+        let mut list = list();
+
+        list.select_page_down(2);
+        assert_eq!(list.get_position(), 2);
+        list.select_page_down(2);
+        assert_eq!(list.get_position(), 2);
+        list.select_page_up(5);
+        assert_eq!(list.get_position(), 0);
+    }
+
+    #[test]
+    fn limit_caps_visible_items_but_not_total_count() {
+        let mut list = list().with_limit(Some(2));
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.total_len(), 3);
+        assert_eq!(list.iter().count(), 2);
+        assert_eq!(list.iter_all().count(), 3);
+
+        list.select_down();
+        list.select_down();
+        assert_eq!(list.get_position(), 0);
+    }
+
+    #[test]
+    fn navigation_wraps_around_the_ends_when_enabled() {
+        let mut list = list();
+        list.select_up();
+        assert_eq!(list.get_position(), 2);
+        list.select_down();
+        assert_eq!(list.get_position(), 0);
+    }
+
+    #[test]
+    fn navigation_stops_at_the_ends_when_wrap_is_disabled() {
+        let mut list = list().with_wrap(false);
+        list.select_up();
+        assert_eq!(list.get_position(), 0);
+        list.select(2);
+        list.select_down();
+        assert_eq!(list.get_position(), 2);
+
+        let mut empty = FilteredList::<Label>::new(Vec::new()).with_wrap(false);
+        empty.select_down();
+        empty.select_up();
+        assert_eq!(empty.get_position(), 0);
+    }
+
+    #[test]
+    fn select_clamps_to_the_last_item() {
+        let mut list = list();
+
+        list.select(1);
+        assert_eq!(list.get_position(), 1);
+        list.select(10);
+        assert_eq!(list.get_position(), 2);
     }
 }
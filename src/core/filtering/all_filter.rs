@@ -0,0 +1,74 @@
+use crate::core::filtering::name_filter::NameFilter;
+use crate::core::filtering::traits::{DescGetter, Filter, LabelsGetter, NameGetter};
+
+// Matches the filter against the name, the description or any label.
+pub struct AllFilter {
+    name_filter: NameFilter,
+    filter: String,
+    ignore_case: bool,
+}
+
+impl AllFilter {
+    pub fn new(filter: String, ignore_case: bool) -> Self {
+        AllFilter {
+            name_filter: NameFilter::new(filter.clone(), ignore_case),
+            filter: if ignore_case {
+                filter.to_lowercase()
+            } else {
+                filter
+            },
+            ignore_case,
+        }
+    }
+
+    fn contains(&self, value: &str) -> bool {
+        if self.ignore_case {
+            value.to_lowercase().contains(&self.filter)
+        } else {
+            value.contains(&self.filter)
+        }
+    }
+}
+
+impl<T: NameGetter + DescGetter + LabelsGetter> Filter<T> for AllFilter {
+    fn keep(&self, getter: &T) -> bool {
+        self.keep_indices(getter).0
+    }
+
+    // Only name matches can be highlighted.
+    fn keep_indices(&self, getter: &T) -> (bool, Vec<usize>) {
+        let (keep, indices) = self.name_filter.keep_indices(getter);
+        if keep {
+            return (true, indices);
+        }
+        let keep = self.contains(&getter.get_desc())
+            || getter.get_labels().iter().any(|label| self.contains(label));
+        (keep, Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AllFilter;
+    use crate::bookmark::Bookmark;
+    use crate::core::filtering::traits::Filter;
+
+    #[test]
+    fn all_filter_matches_name_description_or_labels() {
+        let bookmark = Bookmark {
+            name: "deploy".to_string(),
+            desc: "Ship the App".to_string(),
+            labels: vec!["Kubernetes".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            AllFilter::new("ploy".to_string(), true).keep_indices(&bookmark),
+            (true, vec![2, 3, 4, 5])
+        );
+        assert!(AllFilter::new("app".to_string(), true).keep(&bookmark));
+        assert!(AllFilter::new("kube".to_string(), true).keep(&bookmark));
+        assert!(!AllFilter::new("kube".to_string(), false).keep(&bookmark));
+        assert!(!AllFilter::new("docker".to_string(), true).keep(&bookmark));
+    }
+}
@@ -1,8 +1,10 @@
-use crate::core::filtering::id_filter::IdFilter;
-use crate::core::filtering::label_filter::LabelFilter;
+use crate::core::filtering::all_filter::AllFilter;
+use crate::core::filtering::id_filter::{IdFilter, IdMatch};
+use crate::core::filtering::label_filter::{LabelFilter, NEGATION_PREFIX};
 use crate::core::filtering::mode::FilterMode;
 use crate::core::filtering::name_filter::{NameFilter, NameFuzzyFilter};
-use crate::core::filtering::traits::{Filter, IdGetter, LabelsGetter, NameGetter};
+use crate::core::filtering::regex_filter::RegexFilter;
+use crate::core::filtering::traits::{DescGetter, Filter, IdGetter, LabelsGetter, NameGetter};
 
 pub struct GenericFilter {
     mode: FilterMode,
@@ -10,46 +12,231 @@ pub struct GenericFilter {
     name_fuzzy_filter: NameFuzzyFilter,
     id_filter: IdFilter,
     label_filter: LabelFilter,
+    regex_filter: RegexFilter,
+    all_filter: AllFilter,
     fuzzy: bool,
+    // Set when the name filter starts with the negation prefix.
+    excluded_name: Option<String>,
+    // The name filter without its negation prefix.
+    name: String,
+    // Lets the name filter also match descriptions, as a plain substring.
+    description_term: Option<String>,
+    ignore_case: bool,
 }
 
 impl GenericFilter {
     pub fn new(mode: FilterMode, filter: String, ignore_case: bool, fuzzy: bool) -> Self {
+        // A lone negation prefix is no filter at all.
+        let (name, excluded_name): (String, Option<String>) =
+            match filter.strip_prefix(NEGATION_PREFIX) {
+                Some("") => (String::new(), None),
+                Some(name) => (name.to_string(), Some(name.to_string())),
+                None => (filter.clone(), None),
+            };
         GenericFilter {
             mode,
-            name_filter: NameFilter::new(filter.clone(), ignore_case),
-            name_fuzzy_filter: NameFuzzyFilter::new(filter.clone(), ignore_case),
+            name_filter: NameFilter::new(name.clone(), ignore_case),
+            name_fuzzy_filter: NameFuzzyFilter::new(name.clone(), ignore_case),
             id_filter: IdFilter::new(filter.clone()),
-            label_filter: LabelFilter::new(filter, ignore_case),
+            label_filter: LabelFilter::new(filter.clone(), ignore_case),
+            // The pattern is compiled on every keystroke, so only while it is used.
+            regex_filter: match mode {
+                FilterMode::Regex => RegexFilter::new(filter.clone(), ignore_case),
+                _ => RegexFilter::default(),
+            },
+            all_filter: AllFilter::new(filter, ignore_case),
             fuzzy,
+            excluded_name,
+            name,
+            description_term: None,
+            ignore_case,
+        }
+    }
+
+    // Terms the current filter excludes.
+    pub fn exclusions(&self) -> Vec<String> {
+        match self.mode {
+            FilterMode::Name => self.excluded_name.iter().cloned().collect(),
+            FilterMode::Label => self.label_filter.excluded().to_vec(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn with_label_match_all(mut self, match_all: bool) -> Self {
+        self.label_filter = self.label_filter.with_match_all(match_all);
+        self
+    }
+
+    pub fn with_id_match(mut self, id_match: IdMatch) -> Self {
+        self.id_filter = self.id_filter.with_id_match(id_match);
+        self
+    }
+
+    pub fn with_acronyms(mut self, acronyms: bool) -> Self {
+        self.name_filter = self.name_filter.with_acronyms(acronyms);
+        self
+    }
+
+    pub fn with_label_hierarchy(mut self, separator: Option<String>) -> Self {
+        self.label_filter = self.label_filter.with_hierarchy_separator(separator);
+        self
+    }
+
+    pub fn with_search_description(mut self, search_description: bool) -> Self {
+        self.description_term = (search_description && !self.name.is_empty()).then(|| {
+            if self.ignore_case {
+                self.name.to_lowercase()
+            } else {
+                self.name.clone()
+            }
+        });
+        self
+    }
+
+    fn description_matches<T: DescGetter>(&self, getter: &T) -> bool {
+        let Some(term) = self.description_term.as_ref() else {
+            return false;
+        };
+        let desc = getter.get_desc();
+        if self.ignore_case {
+            desc.to_lowercase().contains(term)
+        } else {
+            desc.contains(term)
         }
     }
 }
 
-impl<T: NameGetter + IdGetter + LabelsGetter> Filter<T> for GenericFilter {
+impl<T: NameGetter + IdGetter + LabelsGetter + DescGetter> Filter<T> for GenericFilter {
     fn keep(&self, getter: &T) -> bool {
         match self.mode {
             FilterMode::Name => {
-                if self.fuzzy {
-                    return self.name_fuzzy_filter.keep(getter);
-                }
-                self.name_filter.keep(getter)
+                let keep = if self.fuzzy {
+                    self.name_fuzzy_filter.keep(getter)
+                } else {
+                    self.name_filter.keep(getter)
+                };
+                let keep = keep || self.description_matches(getter);
+                keep != self.excluded_name.is_some()
             }
             FilterMode::ID => self.id_filter.keep(getter),
             FilterMode::Label => self.label_filter.keep(getter),
+            FilterMode::Regex => self.regex_filter.keep(getter),
+            FilterMode::All => self.all_filter.keep(getter),
         }
     }
 
     fn keep_indices(&self, getter: &T) -> (bool, Vec<usize>) {
         match self.mode {
+            FilterMode::Name if self.excluded_name.is_some() => (self.keep(getter), Vec::new()),
             FilterMode::Name => {
-                if self.fuzzy {
-                    return self.name_fuzzy_filter.keep_indices(getter);
+                let (keep, indices) = if self.fuzzy {
+                    self.name_fuzzy_filter.keep_indices(getter)
+                } else {
+                    self.name_filter.keep_indices(getter)
+                };
+                if !keep && self.description_matches(getter) {
+                    return (true, Vec::new());
                 }
-                self.name_filter.keep_indices(getter)
+                (keep, indices)
             }
             FilterMode::ID => self.id_filter.keep_indices(getter),
             FilterMode::Label => self.label_filter.keep_indices(getter),
+            FilterMode::Regex => self.regex_filter.keep_indices(getter),
+            FilterMode::All => self.all_filter.keep_indices(getter),
         }
     }
+
+    fn keep_scored(&self, getter: &T) -> (bool, i64, Vec<usize>) {
+        match self.mode {
+            FilterMode::Name if self.fuzzy && self.excluded_name.is_none() => {
+                // Description-only matches are listed after all name matches.
+                match self.name_fuzzy_filter.keep_scored(getter) {
+                    (false, _, _) if self.description_matches(getter) => (true, 0, Vec::new()),
+                    scored => scored,
+                }
+            }
+            _ => {
+                let (keep, indices) = self.keep_indices(getter);
+                (keep, 0, indices)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GenericFilter;
+    use crate::bookmark::Bookmark;
+    use crate::core::filtering::mode::FilterMode;
+    use crate::core::filtering::traits::Filter;
+    use crate::label::Label;
+
+    fn label(name: &str) -> Label {
+        Label::new(1, name.to_string())
+    }
+
+    #[test]
+    fn negated_name_filter_keeps_names_without_the_term() {
+        for fuzzy in [false, true] {
+            let filter = GenericFilter::new(FilterMode::Name, "!prod".to_string(), true, fuzzy);
+
+            assert!(filter.keep(&label("docker-dev")));
+            assert!(!filter.keep(&label("docker-prod")));
+            assert_eq!(filter.exclusions(), vec!["prod".to_string()]);
+        }
+
+        let lone = GenericFilter::new(FilterMode::Name, "!".to_string(), true, false);
+        assert!(lone.keep(&label("docker-prod")));
+        assert!(lone.exclusions().is_empty());
+    }
+
+    #[test]
+    fn all_filter_matches_lists_without_labels_or_descriptions() {
+        let all = GenericFilter::new(FilterMode::All, "dock".to_string(), true, false);
+        assert!(all.keep(&label("docker")));
+        assert!(!all.keep(&label("git")));
+
+        let by_label = GenericFilter::new(FilterMode::Label, "docker".to_string(), true, false);
+        assert!(!by_label.keep(&label("docker")));
+    }
+
+    #[test]
+    fn regex_is_only_compiled_in_regex_mode() {
+        let name = GenericFilter::new(FilterMode::Name, "dock.r".to_string(), true, false);
+        assert!(!name.regex_filter.is_valid());
+
+        let regex = GenericFilter::new(FilterMode::Regex, "dock.r".to_string(), true, false);
+        assert!(regex.regex_filter.is_valid());
+        assert!(regex.keep(&label("docker")));
+    }
+
+    #[test]
+    fn name_filter_can_also_match_descriptions() {
+        let bookmark = Bookmark {
+            name: "deploy".to_string(),
+            desc: "Ship to production".to_string(),
+            ..Default::default()
+        };
+
+        for fuzzy in [false, true] {
+            let plain = GenericFilter::new(FilterMode::Name, "Production".to_string(), true, fuzzy);
+            assert!(!plain.keep(&bookmark));
+
+            let with_desc =
+                GenericFilter::new(FilterMode::Name, "Production".to_string(), true, fuzzy)
+                    .with_search_description(true);
+            assert_eq!(with_desc.keep_scored(&bookmark), (true, 0, Vec::new()));
+        }
+    }
+
+    #[test]
+    fn description_search_keeps_items_without_descriptions_by_name() {
+        let filter = |text: &str| {
+            GenericFilter::new(FilterMode::Name, text.to_string(), true, false)
+                .with_search_description(true)
+        };
+
+        assert!(filter("dock").keep(&label("docker")));
+        assert!(!filter("prod").keep(&label("docker")));
+    }
 }
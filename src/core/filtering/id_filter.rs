@@ -1,23 +1,117 @@
 use crate::core::filtering::traits::{Filter, IdGetter};
+use std::str::FromStr;
+
+// How the typed id is compared with the ids of the items.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum IdMatch {
+    #[default]
+    Prefix,
+    Exact,
+    Contains,
+}
+
+impl FromStr for IdMatch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "prefix" => Ok(Self::Prefix),
+            "exact" => Ok(Self::Exact),
+            "contains" => Ok(Self::Contains),
+            other => Err(format!("unknown id match '{}'", other)),
+        }
+    }
+}
 
 pub struct IdFilter {
     filter: String,
+    id_match: IdMatch,
 }
 
 impl IdFilter {
     pub fn new(filter: String) -> Self {
-        IdFilter { filter }
+        IdFilter {
+            filter,
+            id_match: IdMatch::default(),
+        }
+    }
+
+    pub fn with_id_match(mut self, id_match: IdMatch) -> Self {
+        self.id_match = id_match;
+        self
+    }
+}
+
+// Separates the ends of an inclusive id range, e.g. "10-20".
+pub const ID_RANGE_SEPARATOR: char = '-';
+
+impl IdFilter {
+    // A range half typed as "10-" is matched like its start, a malformed one
+    // matches nothing.
+    fn keep_range(&self, id: usize, start: &str, end: &str) -> bool {
+        match (start.trim().parse::<usize>(), end.trim().parse::<usize>()) {
+            (Ok(start), Ok(end)) => (start..=end).contains(&id),
+            (Ok(_), Err(_)) if end.trim().is_empty() => id.to_string().starts_with(start.trim()),
+            _ => false,
+        }
     }
 }
 
 impl<T: IdGetter> Filter<T> for IdFilter {
     fn keep(&self, getter: &T) -> bool {
-        getter
-            .get_id()
-            .to_string()
-            .starts_with(&self.filter.to_string())
+        if let Some((start, end)) = self.filter.split_once(ID_RANGE_SEPARATOR) {
+            return self.keep_range(getter.get_id(), start, end);
+        }
+        let id = getter.get_id().to_string();
+        match self.id_match {
+            IdMatch::Prefix => id.starts_with(&self.filter),
+            // An empty filter keeps everything, like the other matches.
+            IdMatch::Exact => self.filter.is_empty() || id == self.filter,
+            IdMatch::Contains => id.contains(&self.filter),
+        }
     }
     fn keep_indices(&self, getter: &T) -> (bool, Vec<usize>) {
         (self.keep(getter), Vec::new())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{IdFilter, IdMatch};
+    use crate::core::filtering::traits::{Filter, IdGetter};
+
+    struct Item(usize);
+
+    impl IdGetter for Item {
+        fn get_id(&self) -> usize {
+            self.0
+        }
+    }
+
+    fn kept(filter: &str, id_match: IdMatch) -> Vec<usize> {
+        let filter = IdFilter::new(filter.to_string()).with_id_match(id_match);
+        [2, 12, 20, 21]
+            .into_iter()
+            .filter(|id| filter.keep(&Item(*id)))
+            .collect()
+    }
+
+    #[test]
+    fn ranges_match_the_ids_between_their_ends() {
+        assert_eq!(kept("12-20", IdMatch::Prefix), vec![12, 20]);
+        assert_eq!(kept("3-21", IdMatch::Exact), vec![12, 20, 21]);
+        assert_eq!(kept("2-", IdMatch::Prefix), vec![2, 20, 21]);
+        assert!(kept("20-12", IdMatch::Prefix).is_empty());
+        assert!(kept("a-3", IdMatch::Prefix).is_empty());
+    }
+
+    #[test]
+    fn ids_match_by_prefix_exactly_or_anywhere() {
+        assert_eq!(kept("2", IdMatch::Prefix), vec![2, 20, 21]);
+        assert_eq!(kept("2", IdMatch::Exact), vec![2]);
+        assert_eq!(kept("2", IdMatch::Contains), vec![2, 12, 20, 21]);
+        assert_eq!(kept("", IdMatch::Exact), vec![2, 12, 20, 21]);
+        assert_eq!("exact".parse::<IdMatch>(), Ok(IdMatch::Exact));
+        assert!("suffix".parse::<IdMatch>().is_err());
+    }
+}
@@ -1,37 +1,317 @@
 use crate::core::filtering::traits::{Filter, LabelsGetter};
 
+// Separates several labels in one filter string. Labels may contain spaces,
+// so a comma is used instead.
+const LABEL_SEPARATOR: char = ',';
+// Inverts the match of a filter term.
+pub(crate) const NEGATION_PREFIX: char = '!';
+
 pub struct LabelFilter {
-    filter: String,
+    terms: Vec<String>,
+    excluded: Vec<String>,
     ignore_case: bool,
+    match_all: bool,
+    // With a separator, a term also matches the labels nested under it.
+    hierarchy_separator: Option<String>,
 }
 
 impl LabelFilter {
     pub(crate) fn new(filter: String, ignore_case: bool) -> Self {
+        let mut terms = Vec::new();
+        let mut excluded = Vec::new();
+        for term in filter.split(LABEL_SEPARATOR).map(str::trim) {
+            let term = if ignore_case {
+                term.to_lowercase()
+            } else {
+                term.to_string()
+            };
+            match term.strip_prefix(NEGATION_PREFIX) {
+                Some(label) if !label.trim().is_empty() => excluded.push(label.trim().to_string()),
+                Some(_) => {}
+                None if !term.is_empty() => terms.push(term),
+                None => {}
+            }
+        }
         LabelFilter {
-            filter,
+            terms,
+            excluded,
             ignore_case,
+            match_all: false,
+            hierarchy_separator: None,
         }
     }
+
+    pub(crate) fn excluded(&self) -> &[String] {
+        &self.excluded
+    }
+
+    // Requires every label of the filter instead of any of them.
+    pub(crate) fn with_match_all(mut self, match_all: bool) -> Self {
+        self.match_all = match_all;
+        self
+    }
+
+    pub(crate) fn with_hierarchy_separator(mut self, separator: Option<String>) -> Self {
+        self.hierarchy_separator = separator.filter(|separator| !separator.is_empty());
+        self
+    }
+
+    fn has_label(&self, labels: &[String], term: &str) -> bool {
+        labels.iter().any(|label| {
+            let label = if self.ignore_case {
+                label.to_lowercase()
+            } else {
+                label.to_string()
+            };
+            label == term
+                || self.hierarchy_separator.as_ref().is_some_and(|separator| {
+                    label
+                        .strip_prefix(term)
+                        .is_some_and(|rest| rest.starts_with(separator.as_str()))
+                })
+        })
+    }
+}
+
+// Splits off the label being typed, after the last separator and any
+// whitespace or negation prefix.
+fn split_last_term(filter: &str) -> (&str, &str) {
+    let start = filter.rfind(LABEL_SEPARATOR).map_or(0, |i| i + 1);
+    let term = filter[start..]
+        .trim_start()
+        .trim_start_matches(NEGATION_PREFIX)
+        .trim_start();
+    filter.split_at(filter.len() - term.len())
+}
+
+// Labels starting with the label being typed, sorted and without duplicates.
+pub fn label_completions(filter: &str, labels: &[String], ignore_case: bool) -> Vec<String> {
+    let (_, term) = split_last_term(filter);
+    if term.is_empty() {
+        return Vec::new();
+    }
+    let normalize = |value: &str| {
+        if ignore_case {
+            value.to_lowercase()
+        } else {
+            value.to_string()
+        }
+    };
+    let term = normalize(term);
+    let mut completions: Vec<String> = labels
+        .iter()
+        .filter(|label| normalize(label).starts_with(&term))
+        .cloned()
+        .collect();
+    completions.sort();
+    completions.dedup();
+    completions
+}
+
+// Completes the label being typed to the longest prefix its completions share.
+pub fn complete_label_filter(filter: &str, labels: &[String], ignore_case: bool) -> Option<String> {
+    let completions = label_completions(filter, labels, ignore_case);
+    let (first, rest) = completions.split_first()?;
+    let mut prefix: Vec<char> = first.chars().collect();
+    for label in rest {
+        let shared = prefix
+            .iter()
+            .zip(label.chars())
+            .take_while(|(a, b)| {
+                if ignore_case {
+                    a.to_lowercase().eq(b.to_lowercase())
+                } else {
+                    **a == *b
+                }
+            })
+            .count();
+        prefix.truncate(shared);
+    }
+
+    let (head, term) = split_last_term(filter);
+    if prefix.len() <= term.chars().count() {
+        return None;
+    }
+    Some(format!(
+        "{}{}",
+        head,
+        prefix.into_iter().collect::<String>()
+    ))
+}
+
+// Labels containing the label being typed, those starting with it first. The
+// label already typed in full isn't suggested.
+pub fn label_suggestions(filter: &str, labels: &[String], ignore_case: bool) -> Vec<String> {
+    let (_, term) = split_last_term(filter);
+    if term.is_empty() {
+        return Vec::new();
+    }
+    let normalize = |value: &str| {
+        if ignore_case {
+            value.to_lowercase()
+        } else {
+            value.to_string()
+        }
+    };
+    let term = normalize(term);
+    let mut suggestions: Vec<(bool, String)> = labels
+        .iter()
+        .filter_map(|label| {
+            let normalized = normalize(label);
+            (normalized != term && normalized.contains(&term))
+                .then(|| (!normalized.starts_with(&term), label.clone()))
+        })
+        .collect();
+    suggestions.sort();
+    suggestions.dedup();
+    suggestions.into_iter().map(|(_, label)| label).collect()
+}
+
+// Replaces the label being typed with the given one.
+pub fn fill_label_filter(filter: &str, label: &str) -> String {
+    let (head, _) = split_last_term(filter);
+    format!("{}{}", head, label)
 }
 
 impl<T: LabelsGetter> Filter<T> for LabelFilter {
     fn keep(&self, getter: &T) -> bool {
-        if self.filter.is_empty() {
+        if self.terms.is_empty() && self.excluded.is_empty() {
             return true;
         }
-        for label in getter.get_labels().iter() {
-            let (f, l) = if self.ignore_case {
-                (self.filter.to_lowercase(), label.to_lowercase())
-            } else {
-                (self.filter.clone(), label.clone())
-            };
-            if f == l {
-                return true;
-            }
+        let labels = getter.get_labels();
+        if self
+            .excluded
+            .iter()
+            .any(|term| self.has_label(&labels, term))
+        {
+            return false;
+        }
+        if self.terms.len() <= 1 {
+            return self
+                .terms
+                .first()
+                .is_none_or(|term| self.has_label(&labels, term));
+        }
+        if self.match_all {
+            self.terms.iter().all(|term| self.has_label(&labels, term))
+        } else {
+            self.terms.iter().any(|term| self.has_label(&labels, term))
         }
-        false
     }
     fn keep_indices(&self, getter: &T) -> (bool, Vec<usize>) {
         (self.keep(getter), Vec::new())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        complete_label_filter, fill_label_filter, label_completions, label_suggestions, LabelFilter,
+    };
+    use crate::core::filtering::traits::{Filter, LabelsGetter};
+
+    struct Item(Vec<String>);
+
+    impl LabelsGetter for Item {
+        fn get_labels(&self) -> Vec<String> {
+            self.0.clone()
+        }
+    }
+
+    fn item(labels: &[&str]) -> Item {
+        Item(labels.iter().map(|label| label.to_string()).collect())
+    }
+
+    #[test]
+    fn single_label_matches_exactly() {
+        let filter = LabelFilter::new("Docker".to_string(), true);
+
+        assert!(filter.keep(&item(&["docker", "prod"])));
+        assert!(!filter.keep(&item(&["dockerfile"])));
+    }
+
+    #[test]
+    fn several_labels_match_any_or_all() {
+        let any = LabelFilter::new("git, svn".to_string(), true);
+        assert!(any.keep(&item(&["svn"])));
+        assert!(!any.keep(&item(&["hg"])));
+
+        let all = LabelFilter::new("docker,prod".to_string(), true).with_match_all(true);
+        assert!(all.keep(&item(&["prod", "docker"])));
+        assert!(!all.keep(&item(&["docker"])));
+    }
+
+    #[test]
+    fn hierarchical_labels_match_nested_labels() {
+        let filter = LabelFilter::new("Infra".to_string(), true)
+            .with_hierarchy_separator(Some("/".to_string()));
+        assert!(filter.keep(&item(&["infra/aws"])));
+        assert!(filter.keep(&item(&["infra"])));
+        assert!(!filter.keep(&item(&["infrastructure"])));
+
+        let flat = LabelFilter::new("infra".to_string(), true);
+        assert!(!flat.keep(&item(&["infra/aws"])));
+    }
+
+    #[test]
+    fn complete_label_filter_completes_the_last_term_to_the_shared_prefix() {
+        let labels: Vec<String> = ["docker", "docker-compose", "dev", "Deploy"]
+            .iter()
+            .map(|label| label.to_string())
+            .collect();
+
+        assert_eq!(
+            complete_label_filter("doc", &labels, true),
+            Some("docker".to_string())
+        );
+        assert_eq!(
+            complete_label_filter("prod, !dep", &labels, true),
+            Some("prod, !Deploy".to_string())
+        );
+        assert_eq!(complete_label_filter("docker", &labels, true), None);
+        assert_eq!(complete_label_filter("d", &labels, true), None);
+        assert_eq!(complete_label_filter("prod, ", &labels, true), None);
+
+        assert_eq!(
+            label_completions("prod,d", &labels, true),
+            vec!["Deploy", "dev", "docker", "docker-compose"]
+        );
+        assert_eq!(label_completions("D", &labels, false), vec!["Deploy"]);
+    }
+
+    #[test]
+    fn label_suggestions_list_prefix_matches_before_other_matches() {
+        let labels: Vec<String> = ["dev", "kube-dev", "Devops", "dev", "prod"]
+            .iter()
+            .map(|label| label.to_string())
+            .collect();
+
+        assert_eq!(
+            label_suggestions("prod, de", &labels, true),
+            vec!["Devops", "dev", "kube-dev"]
+        );
+        assert_eq!(
+            label_suggestions("dev", &labels, true),
+            vec!["Devops", "kube-dev"]
+        );
+        assert_eq!(label_suggestions("dev", &labels, false), vec!["kube-dev"]);
+        assert!(label_suggestions("prod, ", &labels, true).is_empty());
+
+        assert_eq!(fill_label_filter("prod, !de", "dev"), "prod, !dev");
+    }
+
+    #[test]
+    fn negated_labels_are_excluded() {
+        let filter = LabelFilter::new("docker, !prod".to_string(), true);
+        assert!(filter.keep(&item(&["docker", "dev"])));
+        assert!(!filter.keep(&item(&["docker", "prod"])));
+        assert_eq!(filter.excluded(), ["prod"]);
+
+        let only_excluded = LabelFilter::new("!dangerous".to_string(), true);
+        assert!(only_excluded.keep(&item(&["safe"])));
+        assert!(!only_excluded.keep(&item(&["dangerous"])));
+
+        let lone = LabelFilter::new("!".to_string(), true);
+        assert!(lone.keep(&item(&["dangerous"])));
+    }
+}
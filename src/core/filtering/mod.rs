@@ -1,10 +1,17 @@
+mod all_filter;
 mod generic_filter;
 mod id_filter;
 mod label_filter;
 mod mode;
 mod name_filter;
+mod regex_filter;
 mod traits;
 
 pub use generic_filter::GenericFilter;
+pub use id_filter::{IdMatch, ID_RANGE_SEPARATOR};
+pub use label_filter::{
+    complete_label_filter, fill_label_filter, label_completions, label_suggestions,
+};
 pub use mode::FilterMode;
-pub use traits::{Filter, IdGetter, LabelsGetter, NameGetter};
+pub use regex_filter::RegexFilter;
+pub use traits::{DescGetter, Filter, IdGetter, LabelsGetter, NameGetter};
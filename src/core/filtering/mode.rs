@@ -1,12 +1,16 @@
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Formatter;
+use std::str::FromStr;
 
-#[derive(Default, PartialEq, Copy, Clone, Debug)]
+#[derive(Default, PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum FilterMode {
     #[default]
     Name,
     ID,
     Label,
+    Regex,
+    All,
 }
 
 impl fmt::Display for FilterMode {
@@ -15,11 +19,28 @@ impl fmt::Display for FilterMode {
             Self::Name => "Name",
             Self::ID => "ID",
             Self::Label => "Label",
+            Self::Regex => "Regex",
+            Self::All => "All",
         };
         write!(f, "{}", name)
     }
 }
 
+impl FromStr for FilterMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "name" => Ok(Self::Name),
+            "id" => Ok(Self::ID),
+            "label" => Ok(Self::Label),
+            "regex" => Ok(Self::Regex),
+            "all" => Ok(Self::All),
+            other => Err(format!("unknown filter mode '{}'", other)),
+        }
+    }
+}
+
 impl FilterMode {
     pub fn switch_to(&self, mode: FilterMode) -> Self {
         if *self == mode {
@@ -2,9 +2,13 @@ use crate::core::filtering::traits::{Filter, NameGetter};
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
 
+// Characters that separate the words of a name for acronym matches.
+const WORD_SEPARATORS: [char; 3] = [' ', '-', '_'];
+
 pub struct NameFilter {
     filter: String,
     ignore_case: bool,
+    acronyms: bool,
 }
 
 impl NameFilter {
@@ -12,30 +16,64 @@ impl NameFilter {
         NameFilter {
             filter,
             ignore_case,
+            acronyms: false,
+        }
+    }
+
+    // Also matches the first letters of consecutive words, e.g. "dpc" for
+    // "Deploy Prod Cluster", when the name doesn't contain the filter.
+    pub fn with_acronyms(mut self, acronyms: bool) -> Self {
+        self.acronyms = acronyms;
+        self
+    }
+
+    fn acronym_indices(&self, name: &str, filter: &str) -> Option<Vec<usize>> {
+        let mut initials = Vec::new();
+        let mut word_start = true;
+        for (i, c) in name.chars().enumerate() {
+            if WORD_SEPARATORS.contains(&c) {
+                word_start = true;
+            } else if word_start {
+                initials.push((i, c));
+                word_start = false;
+            }
         }
+        let filter: Vec<char> = filter.chars().collect();
+        initials
+            .windows(filter.len())
+            .find(|window| window.iter().map(|(_, c)| *c).eq(filter.iter().copied()))
+            .map(|window| window.iter().map(|(i, _)| *i).collect())
     }
 }
 
 impl<T: NameGetter> Filter<T> for NameFilter {
     fn keep(&self, getter: &T) -> bool {
+        self.keep_indices(getter).0
+    }
+
+    fn keep_indices(&self, getter: &T) -> (bool, Vec<usize>) {
         if self.filter.is_empty() {
-            return true;
+            return (true, Vec::new());
         }
         let (filter, name) = if self.ignore_case {
             (self.filter.to_lowercase(), getter.get_name().to_lowercase())
         } else {
             (self.filter.clone(), getter.get_name().clone())
         };
-        name == filter || name.contains(&filter)
-    }
-
-    fn keep_indices(&self, getter: &T) -> (bool, Vec<usize>) {
-        let keep = self.keep(getter);
-        if keep {
-            let indices = (0..self.filter.len()).collect();
-            return (true, indices);
+        match name.find(&filter) {
+            Some(pos) => {
+                let start = name[..pos].chars().count();
+                (true, (start..start + filter.chars().count()).collect())
+            }
+            // A single letter would match the start of nearly any word.
+            None if self.acronyms && filter.chars().count() > 1 => {
+                match self.acronym_indices(&name, &filter) {
+                    Some(indices) => (true, indices),
+                    None => (false, Vec::new()),
+                }
+            }
+            None => (false, Vec::new()),
         }
-        (false, Vec::new())
     }
 }
 
@@ -45,8 +83,12 @@ pub struct NameFuzzyFilter {
 }
 
 impl NameFuzzyFilter {
-    pub fn new(filter: String, _: bool) -> Self {
-        let matcher = SkimMatcherV2::default();
+    pub fn new(filter: String, ignore_case: bool) -> Self {
+        let matcher = if ignore_case {
+            SkimMatcherV2::default().ignore_case()
+        } else {
+            SkimMatcherV2::default().respect_case()
+        };
         NameFuzzyFilter { filter, matcher }
     }
 }
@@ -69,17 +111,88 @@ impl<T: NameGetter> Filter<T> for NameFuzzyFilter {
     }
 
     fn keep_indices(&self, getter: &T) -> (bool, Vec<usize>) {
+        let (keep, _, indices) = self.keep_scored(getter);
+        (keep, indices)
+    }
+
+    fn keep_scored(&self, getter: &T) -> (bool, i64, Vec<usize>) {
         if self.filter.is_empty() {
-            return (true, Vec::new());
+            return (true, 0, Vec::new());
         }
 
         if let Some((score, indices)) = self
             .matcher
             .fuzzy_indices(getter.get_name().as_str(), self.filter.as_str())
         {
-            return (score.is_positive(), indices);
+            return (score.is_positive(), score, indices);
         };
 
-        (false, Vec::new())
+        (false, 0, Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NameFilter, NameFuzzyFilter};
+    use crate::core::filtering::traits::Filter;
+    use crate::label::Label;
+
+    fn label(name: &str) -> Label {
+        Label::new(1, name.to_string())
+    }
+
+    #[test]
+    fn name_filter_returns_indices_of_the_matched_substring() {
+        let filter = NameFilter::new("Compose".to_string(), true);
+
+        assert_eq!(
+            filter.keep_indices(&label("docker-compose-prod")),
+            (true, (7..14).collect())
+        );
+        assert!(!filter.keep(&label("kubectl")));
+    }
+
+    #[test]
+    fn acronyms_match_the_first_letters_of_consecutive_words() {
+        let filter = NameFilter::new("DPC".to_string(), true).with_acronyms(true);
+        assert_eq!(
+            filter.keep_indices(&label("Deploy Prod-Cluster")),
+            (true, vec![0, 7, 12])
+        );
+        assert!(filter.keep(&label("build: deploy_prod_cluster")));
+        assert!(!filter.keep(&label("Deploy Cluster")));
+
+        let case_sensitive = NameFilter::new("dpc".to_string(), false).with_acronyms(true);
+        assert!(!case_sensitive.keep(&label("Deploy Prod Cluster")));
+
+        let plain = NameFilter::new("dpc".to_string(), true);
+        assert!(!plain.keep(&label("Deploy Prod Cluster")));
+    }
+
+    #[test]
+    fn fuzzy_filter_matches_subsequences() {
+        let filter = NameFuzzyFilter::new("dcp".to_string(), true);
+
+        assert!(filter.keep(&label("docker-compose-prod")));
+        assert!(!filter.keep(&label("kubectl")));
+    }
+
+    #[test]
+    fn fuzzy_filter_respects_ignore_case() {
+        let sensitive = NameFuzzyFilter::new("DCP".to_string(), false);
+        let insensitive = NameFuzzyFilter::new("DCP".to_string(), true);
+
+        assert!(!sensitive.keep(&label("docker-compose-prod")));
+        assert!(insensitive.keep(&label("docker-compose-prod")));
+    }
+
+    #[test]
+    fn fuzzy_filter_scores_closer_matches_higher() {
+        let filter = NameFuzzyFilter::new("prod".to_string(), true);
+
+        let (_, exact, _) = filter.keep_scored(&label("prod"));
+        let (_, scattered, _) = filter.keep_scored(&label("p-r-o-d"));
+
+        assert!(exact > scattered);
     }
 }
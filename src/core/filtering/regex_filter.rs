@@ -0,0 +1,85 @@
+use crate::core::filtering::traits::{Filter, NameGetter};
+use regex::{Regex, RegexBuilder};
+
+// The default filter has no pattern and keeps everything.
+#[derive(Default)]
+pub struct RegexFilter {
+    regex: Option<Regex>,
+}
+
+impl RegexFilter {
+    pub fn new(filter: String, ignore_case: bool) -> Self {
+        let regex = RegexBuilder::new(&filter)
+            .case_insensitive(ignore_case)
+            .build()
+            .ok();
+        RegexFilter { regex }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.regex.is_some()
+    }
+}
+
+impl<T: NameGetter> Filter<T> for RegexFilter {
+    fn keep(&self, getter: &T) -> bool {
+        match &self.regex {
+            Some(regex) => regex.is_match(&getter.get_name()),
+            // The pattern is probably still being typed, so keep everything.
+            None => true,
+        }
+    }
+
+    fn keep_indices(&self, getter: &T) -> (bool, Vec<usize>) {
+        let Some(regex) = &self.regex else {
+            return (true, Vec::new());
+        };
+
+        let name = getter.get_name();
+        match regex.find(&name) {
+            Some(m) => {
+                let start = name[..m.start()].chars().count();
+                let len = m.as_str().chars().count();
+                (true, (start..start + len).collect())
+            }
+            None => (false, Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RegexFilter;
+    use crate::core::filtering::traits::Filter;
+    use crate::label::Label;
+
+    fn label(name: &str) -> Label {
+        Label::new(1, name.to_string())
+    }
+
+    #[test]
+    fn regex_filter_matches_names_and_returns_match_indices() {
+        let filter = RegexFilter::new("^kube.*prod$".to_string(), false);
+
+        assert!(filter.keep(&label("kube-prod")));
+        assert!(!filter.keep(&label("kube-dev")));
+
+        let filter = RegexFilter::new("o+".to_string(), false);
+        assert_eq!(filter.keep_indices(&label("foo")), (true, vec![1, 2]));
+    }
+
+    #[test]
+    fn regex_filter_keeps_everything_when_pattern_is_invalid() {
+        let filter = RegexFilter::new("kube[".to_string(), false);
+
+        assert!(!filter.is_valid());
+        assert!(filter.keep(&label("anything")));
+    }
+
+    #[test]
+    fn regex_filter_honors_ignore_case() {
+        let filter = RegexFilter::new("PROD".to_string(), true);
+
+        assert!(filter.keep(&label("kube-prod")));
+    }
+}
@@ -1,6 +1,11 @@
 pub trait Filter<T> {
     fn keep(&self, t: &T) -> bool;
     fn keep_indices(&self, getter: &T) -> (bool, Vec<usize>);
+    // Higher scores are listed first. Filters without ranking score everything equally.
+    fn keep_scored(&self, getter: &T) -> (bool, i64, Vec<usize>) {
+        let (keep, indices) = self.keep_indices(getter);
+        (keep, 0, indices)
+    }
 }
 
 pub trait NameGetter {
@@ -12,5 +17,10 @@ pub trait IdGetter {
 }
 
 pub trait LabelsGetter {
+    // Items without labels return none, so label filters never match them.
     fn get_labels(&self) -> Vec<String>;
 }
+
+pub trait DescGetter {
+    fn get_desc(&self) -> String;
+}
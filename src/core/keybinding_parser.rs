@@ -4,14 +4,30 @@ use std::fmt::Formatter;
 use std::str::FromStr;
 use zellij_tile::prelude::*;
 
+// The last token is the key, e.g. `Ctrl Shift e`, `Alt Enter` or `F5`; all tokens
+// before it are modifiers.
 pub fn parse_key_info(binding: &String) -> Result<Keybinding, Box<dyn std::error::Error>> {
-    let parts: Vec<&str> = binding.split_whitespace().collect();
-    if parts.len() != 2 {
-        return Err(format!("Invalid keybinding format: {}", binding).into());
+    let mut parts: Vec<&str> = binding.split_whitespace().collect();
+    let key = parts
+        .pop()
+        .ok_or_else(|| format!("Invalid keybinding format: '{}' is empty", binding))?;
+    let bare_key = BareKey::from_str(key)
+        .map_err(|_| format!("Unknown key '{}' in keybinding '{}'", key, binding))?;
+
+    let mut modifiers = BTreeSet::new();
+    for part in parts {
+        let modifier = KeyModifier::from_str(part).map_err(|_| {
+            format!(
+                "Unknown key modifier '{}' in keybinding '{}'",
+                part, binding
+            )
+        })?;
+        modifiers.insert(modifier);
     }
-    let modifier = KeyModifier::from_str(parts[0])?;
-    let key_char = parts[1].chars().next().ok_or("Missing key character")?;
-    Ok(Keybinding::new(modifier, key_char))
+
+    Ok(Keybinding {
+        key_with_modifier: KeyWithModifier::new_with_modifiers(bare_key, modifiers),
+    })
 }
 
 #[derive(Clone)]
@@ -29,6 +45,16 @@ impl Keybinding {
         }
     }
 
+    pub fn from_bare_key(key: BareKey) -> Self {
+        Self {
+            key_with_modifier: KeyWithModifier::new(key),
+        }
+    }
+
+    pub(crate) fn key(&self) -> &KeyWithModifier {
+        &self.key_with_modifier
+    }
+
     pub(crate) fn matches(&self, key: &KeyWithModifier) -> bool {
         self.key_with_modifier.eq(key)
     }
@@ -39,3 +65,53 @@ impl fmt::Display for Keybinding {
         self.key_with_modifier.fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_key_info;
+    use std::collections::BTreeSet;
+    use zellij_tile::prelude::*;
+
+    fn key(bare_key: BareKey, modifiers: &[KeyModifier]) -> KeyWithModifier {
+        KeyWithModifier::new_with_modifiers(bare_key, modifiers.iter().copied().collect())
+    }
+
+    #[test]
+    fn parse_key_info_accepts_modifiers_and_named_keys() {
+        let cases = [
+            ("Ctrl e", key(BareKey::Char('e'), &[KeyModifier::Ctrl])),
+            (
+                "Ctrl Shift e",
+                key(BareKey::Char('e'), &[KeyModifier::Ctrl, KeyModifier::Shift]),
+            ),
+            ("Alt Enter", key(BareKey::Enter, &[KeyModifier::Alt])),
+            ("Ctrl F5", key(BareKey::F(5), &[KeyModifier::Ctrl])),
+            ("Space", key(BareKey::Char(' '), &[])),
+            (
+                "Down",
+                KeyWithModifier::new_with_modifiers(BareKey::Down, BTreeSet::new()),
+            ),
+        ];
+        for (binding, expected) in cases {
+            let keybinding = parse_key_info(&binding.to_string()).unwrap();
+            assert!(keybinding.matches(&expected), "{}", binding);
+        }
+    }
+
+    #[test]
+    fn parse_key_info_reports_unknown_tokens() {
+        let err = parse_key_info(&"Hyper e".to_string()).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Unknown key modifier 'Hyper' in keybinding 'Hyper e'"
+        );
+
+        let err = parse_key_info(&"Ctrl F13".to_string()).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Unknown key 'F13' in keybinding 'Ctrl F13'"
+        );
+
+        assert!(parse_key_info(&" ".to_string()).is_err());
+    }
+}
@@ -5,7 +5,13 @@ pub mod ui;
 
 // Re-export commonly used types for convenience
 pub use data::FilteredList;
-pub use filtering::{Filter, FilterMode, GenericFilter, IdGetter, LabelsGetter, NameGetter};
+pub use filtering::{
+    complete_label_filter, fill_label_filter, label_completions, label_suggestions, DescGetter,
+    Filter, FilterMode, GenericFilter, IdGetter, IdMatch, LabelsGetter, NameGetter, RegexFilter,
+    ID_RANGE_SEPARATOR,
+};
 pub use ui::{
-    render_main_menu, render_mode, ErrorManager, UiStyle, RESERVE_COLUMN_COUNT, RESERVE_ROW_COUNT,
+    fit_column, menu_height, render_describe_menu, render_footer, render_main_menu, render_mode,
+    render_popup, render_suggestions, take_width, ErrorManager, MenuHeader, ThemeColors, UiStyle,
+    RESERVE_COLUMN_COUNT,
 };
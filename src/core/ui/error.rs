@@ -1,4 +1,3 @@
-use owo_colors::OwoColorize;
 use zellij_tile::prelude::{print_text_with_coordinates, Text};
 
 pub struct ErrorManager {
@@ -6,6 +5,13 @@ pub struct ErrorManager {
     crit_error: Option<String>,
 }
 
+const ERROR_PREFIX: &str = "ERROR: ";
+
+// The theme's error color is used, so the message stays readable on light and dark themes.
+fn error_text(error: &str) -> Text {
+    Text::new(format!("{}{}", ERROR_PREFIX, error)).error_color_range(ERROR_PREFIX.len()..)
+}
+
 impl ErrorManager {
     pub fn new() -> Self {
         ErrorManager {
@@ -26,12 +32,12 @@ impl ErrorManager {
 
     pub fn render(&mut self) -> bool {
         if let Some(e) = self.crit_error.as_ref() {
-            let text = Text::new(format!("ERROR: {}", e.red()));
+            let text = error_text(e);
             print_text_with_coordinates(text, 1, 1, None, None);
             return true;
         }
         if let Some(e) = self.error.take() {
-            let text = Text::new(format!("ERROR: {}", e.red()));
+            let text = error_text(&e);
             print_text_with_coordinates(text, 1, 1, None, None);
             return true;
         }
@@ -2,4 +2,8 @@ pub mod error;
 pub mod render;
 
 pub use error::ErrorManager;
-pub use render::{render_main_menu, render_mode, UiStyle, RESERVE_COLUMN_COUNT, RESERVE_ROW_COUNT};
+pub use render::{
+    fit_column, menu_height, render_describe_menu, render_footer, render_main_menu, render_mode,
+    render_popup, render_suggestions, take_width, MenuHeader, ThemeColors, UiStyle,
+    RESERVE_COLUMN_COUNT,
+};
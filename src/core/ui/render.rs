@@ -1,129 +1,533 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 use zellij_tile::prelude::*;
 
-pub const RESERVE_ROW_COUNT: usize = 6;
+// The mode line, the search line and the counter above the list.
+const HEADER_ROW_COUNT: usize = 3;
+// The counters below the list.
+const COUNTER_ROW_COUNT: usize = 1;
 pub const RESERVE_COLUMN_COUNT: usize = 36;
 
+// What is drawn above the list. The notice line below the mode line holds
+// notices, confirmations and the dry run marker, and only takes a row while
+// one of them is shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MenuHeader {
+    pub notice: bool,
+}
+
+impl MenuHeader {
+    fn rows(&self) -> usize {
+        HEADER_ROW_COUNT + usize::from(self.notice)
+    }
+
+    fn search_row(&self) -> usize {
+        1 + usize::from(self.notice)
+    }
+
+    // Smallest pane the menu is drawn in: its fixed rows and a single list row.
+    pub fn reserve_rows(&self) -> usize {
+        self.rows() + COUNTER_ROW_COUNT + 1
+    }
+}
+
+const ROW_MARKER: &str = "●";
+
+// Used when neither the config nor the theme decides the color.
+const MATCH_COLOR: usize = 3;
+const ACTIVE_ITEM_COLOR: usize = 0;
+
 #[derive(Clone, Copy, Debug)]
 pub struct UiStyle {
     pub chrome_color: usize,
-    pub match_color: usize,
-    pub active_item_color: usize,
+    // Set from the config. While unset, the mode header and the selected row
+    // follow the theme.
+    pub match_color: Option<usize>,
+    pub active_item_color: Option<usize>,
     pub selected_item_frame: bool,
+    pub theme: Option<ThemeColors>,
 }
 
 impl Default for UiStyle {
     fn default() -> Self {
         Self {
             chrome_color: 2,
-            match_color: 3,
-            active_item_color: 0,
+            match_color: None,
+            active_item_color: None,
             selected_item_frame: true,
+            theme: None,
+        }
+    }
+}
+
+impl UiStyle {
+    pub fn match_color(&self) -> usize {
+        self.match_color.unwrap_or(MATCH_COLOR)
+    }
+
+    // The text of the selected row, drawn on the selection background when
+    // it is framed.
+    pub fn active_item_color(&self) -> usize {
+        let themed = self.theme.map(|theme| match self.selected_item_frame {
+            true => theme.selected,
+            false => theme.unselected,
+        });
+        self.active_item_color
+            .or(themed)
+            .unwrap_or(ACTIVE_ITEM_COLOR)
+    }
+
+    // The keys in the mode header.
+    pub fn mode_color(&self) -> usize {
+        self.match_color
+            .or(self.theme.map(|theme| theme.unselected))
+            .unwrap_or(MATCH_COLOR)
+    }
+}
+
+// The emphasis colors that stand out most on the theme's selected and
+// unselected backgrounds, so the selection and the mode header stay readable
+// on light and dark themes alike.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThemeColors {
+    pub selected: usize,
+    pub unselected: usize,
+}
+
+impl ThemeColors {
+    // Only RGB colors can be compared, other palettes keep the fallback colors.
+    pub fn from_styling(colors: &Styling) -> Option<Self> {
+        Some(Self {
+            selected: most_contrasting_emphasis(&colors.text_selected)?,
+            unselected: most_contrasting_emphasis(&colors.text_unselected)?,
+        })
+    }
+}
+
+fn most_contrasting_emphasis(style: &StyleDeclaration) -> Option<usize> {
+    let background = luminance(style.background)?;
+    let emphases = [
+        style.emphasis_0,
+        style.emphasis_1,
+        style.emphasis_2,
+        style.emphasis_3,
+    ];
+    let mut best: Option<(usize, f64)> = None;
+    for (i, color) in emphases.into_iter().enumerate() {
+        let color = luminance(color)?;
+        let contrast = (color.max(background) + 0.05) / (color.min(background) + 0.05);
+        if best.is_none_or(|(_, best)| contrast > best) {
+            best = Some((i, contrast));
         }
     }
+    best.map(|(i, _)| i)
+}
+
+// The relative luminance contrast ratios are computed from.
+fn luminance(color: PaletteColor) -> Option<f64> {
+    let PaletteColor::Rgb((r, g, b)) = color else {
+        return None;
+    };
+    let channel = |c: u8| {
+        let c = f64::from(c) / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    Some(0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b))
 }
 
 #[allow(clippy::too_many_arguments)]
-pub fn render_main_menu<'a, T: std::fmt::Display + PartialEq + Copy>(
+pub fn render_main_menu<T: std::fmt::Display + PartialEq + Copy>(
     rows: usize,
     cols: usize,
     selected: usize,
     count: usize,
+    total: usize,
     mode: T,
     all_modes: &[T],
     ui_style: &UiStyle,
+    header: MenuHeader,
     filter: String,
     filter_by: String,
-    iterator: impl Iterator<Item = (usize, usize, &'a String, Vec<usize>)>,
+    id_match_len: usize,
+    wrap_selected: bool,
+    headers: &HashMap<usize, String>,
+    iterator: impl Iterator<Item = (usize, usize, String, Vec<usize>, Option<usize>)>,
 ) {
-    let (x, y, width, height) = main_menu_size(rows, cols);
+    let (x, y, width, height) = main_menu_size(rows, cols, header);
 
     render_mode(x, y, mode, all_modes, ui_style);
 
-    render_search_block(x + 2, y + 2, filter, filter_by, ui_style);
+    let search_row = y + header.search_row();
+    render_search_block(x + 2, search_row, filter, filter_by, count, total, ui_style);
 
-    let (begin, end) = if selected >= height {
-        (selected + 1 - height, selected)
-    } else {
-        (0, height.saturating_sub(1))
-    };
+    let rows_iter = iterator.map(|(i, id, value, indices, marker)| {
+        let texts = if wrap_selected && selected == i {
+            prepare_wrapped_row_text(
+                value,
+                id,
+                width,
+                height,
+                indices,
+                id_match_len,
+                marker,
+                ui_style,
+            )
+        } else {
+            vec![prepare_row_text(
+                value,
+                id,
+                width,
+                selected == i,
+                indices,
+                id_match_len,
+                marker,
+                ui_style,
+            )]
+        };
+        match headers.get(&i) {
+            Some(header) => {
+                let header =
+                    Text::new(format!("  {}", header)).color_range(ui_style.chrome_color, ..);
+                (i, std::iter::once(header).chain(texts).collect())
+            }
+            None => (i, texts),
+        }
+    });
+    let menu_rows = rows_iter.collect::<Vec<_>>();
 
-    render_right_counter(begin, width, y + 3, ui_style);
+    // Lines taken by headers and the expanded selection are not available to other rows.
+    let lines: Vec<usize> = menu_rows.iter().map(|(_, texts)| texts.len()).collect();
+    let (begin, end) = visible_rows(&lines, selected, height);
 
+    render_right_counter(begin, width, y + header.rows() - 1, ui_style);
+
+    let mut rendered = 0;
     {
-        let mut number = y + 4;
+        let mut number = y + header.rows();
 
-        for (i, id, value, indices) in iterator {
+        for (i, texts) in menu_rows {
             if i < begin {
                 continue;
             }
             if i > end {
                 break;
             }
-            let text = prepare_row_text(value.clone(), id, width, selected == i, indices, ui_style);
+            for text in texts {
+                print_text_with_coordinates(text, x, number, None, None);
+                number += 1;
+            }
+            rendered += 1;
+        }
+    }
+
+    let counter_row = y + header.rows() + height;
+    render_all_counter(x + 2, counter_row, count, ui_style);
+
+    // Covers items below the window as well as items hidden by the results limit.
+    let hidden = count.saturating_sub(begin + rendered);
+    render_right_counter_with_max(hidden, count, width, counter_row, ui_style);
+}
+
+pub fn menu_height(rows: usize, header: MenuHeader) -> usize {
+    main_menu_size(rows, 0, header).3
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn render_describe_menu<T: std::fmt::Display + PartialEq + Copy>(
+    rows: usize,
+    cols: usize,
+    selected: usize,
+    count: usize,
+    total: usize,
+    mode: T,
+    all_modes: &[T],
+    ui_style: &UiStyle,
+    header: MenuHeader,
+    filter: String,
+    filter_by: String,
+    id_match_len: usize,
+    iterator: impl Iterator<Item = (usize, usize, String, String, Vec<usize>)>,
+) {
+    let (x, y, width, height) = main_menu_size(rows, cols, header);
+
+    render_mode(x, y, mode, all_modes, ui_style);
+
+    let search_row = y + header.search_row();
+    render_search_block(x + 2, search_row, filter, filter_by, count, total, ui_style);
+
+    let blocks = iterator
+        .map(|(i, id, name, desc, indices)| {
+            let lines = if desc.trim().is_empty() {
+                vec![format!("{}(no description)", DESCRIBE_INDENT)]
+            } else {
+                wrap_text(&desc, width.saturating_sub(DESCRIBE_INDENT.len()))
+                    .into_iter()
+                    .map(|line| format!("{}{}", DESCRIBE_INDENT, line))
+                    .collect()
+            };
+            (i, id, name, lines, indices)
+        })
+        .collect::<Vec<_>>();
+
+    // Walk back from the selected block until the window is full.
+    let mut begin = selected.min(blocks.len().saturating_sub(1));
+    let mut used = blocks.get(begin).map_or(0, |b| b.3.len() + 1);
+    while begin > 0 && used + blocks[begin - 1].3.len() < height {
+        begin -= 1;
+        used += blocks[begin].3.len() + 1;
+    }
 
-            print_text_with_coordinates(text, x, number, None, None);
+    render_right_counter(begin, width, y + header.rows() - 1, ui_style);
 
+    let mut number = y + header.rows();
+    let last_row = y + header.rows() + height;
+    let mut rendered = begin;
+
+    for (i, id, name, lines, indices) in blocks.into_iter().skip(begin) {
+        if number >= last_row {
+            break;
+        }
+        let text = prepare_row_text(
+            name,
+            id,
+            width,
+            selected == i,
+            indices,
+            id_match_len,
+            None,
+            ui_style,
+        );
+        print_text_with_coordinates(text, x, number, None, None);
+        number += 1;
+
+        for line in lines {
+            if number >= last_row {
+                break;
+            }
+            print_text_with_coordinates(Text::new(line), x, number, None, None);
             number += 1;
         }
+        rendered += 1;
+    }
+
+    let counter_row = y + header.rows() + height;
+    render_all_counter(x + 2, counter_row, count, ui_style);
+
+    if count > rendered {
+        render_right_counter_with_max(count - rendered, count, width, counter_row, ui_style);
+    }
+}
+
+const DESCRIBE_INDENT: &str = "    ";
+
+// Greedy word wrap by display width, preserving explicit line breaks.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word = word.to_string();
+            while word.width() > width {
+                if !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                // A wide character in a one column line still takes a line.
+                let (head, len) = match take_width(&word, width) {
+                    (_, 0) => (word.chars().take(1).collect(), 1),
+                    taken => taken,
+                };
+                lines.push(head);
+                word = word.chars().skip(len).collect();
+            }
+            if word.is_empty() {
+                continue;
+            }
+            if line.is_empty() {
+                line = word;
+            } else if line.width() + 1 + word.width() <= width {
+                line.push(' ');
+                line.push_str(&word);
+            } else {
+                lines.push(std::mem::replace(&mut line, word));
+            }
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Truncates the text with an ellipsis or pads it with spaces to the width.
+// Returns the column and how many of its characters are left of the text.
+pub fn fit_column(text: &str, width: usize) -> (String, usize) {
+    let text_width = text.width();
+    if text_width > width {
+        let (mut column, visible) = take_width(text, width.saturating_sub(3));
+        column.push_str(&".".repeat(width.min(3)));
+        // A wide character that doesn't fit leaves a gap before the ellipsis.
+        let gap = width.saturating_sub(column.width());
+        column.insert_str(column.len() - width.min(3), &" ".repeat(gap));
+        (column, visible)
+    } else {
+        (
+            format!("{}{}", text, " ".repeat(width - text_width)),
+            text.chars().count(),
+        )
     }
+}
 
-    render_all_counter(x + 2, rows, count, ui_style);
+// The longest start of the text that takes at most `width` columns, with
+// wide characters like CJK and most emoji taking two, and its length in
+// characters.
+pub fn take_width(text: &str, width: usize) -> (String, usize) {
+    let mut used = 0;
+    let taken: String = text
+        .chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= width
+        })
+        .collect();
+    let len = taken.chars().count();
+    (taken, len)
+}
 
-    if count > end {
-        render_right_counter_with_max(count - 1 - end, count, width, rows, ui_style);
+// First and last row index shown for rows of several lines. Rows above the
+// selection are only shown while they fit, then the rows below fill the rest.
+fn visible_rows(lines: &[usize], selected: usize, height: usize) -> (usize, usize) {
+    if selected >= lines.len() {
+        return (0, 0);
+    }
+    let mut begin = selected;
+    let mut used = lines[selected];
+    while begin > 0 && used + lines[begin - 1] <= height {
+        begin -= 1;
+        used += lines[begin];
+    }
+    let mut end = selected;
+    while end + 1 < lines.len() && used + lines[end + 1] <= height {
+        end += 1;
+        used += lines[end];
     }
+    (begin, end)
 }
 
-fn main_menu_size(rows: usize, cols: usize) -> (usize, usize, usize, usize) {
+// First and last row index shown, with the selection kept in the window.
+fn visible_range(selected: usize, height: usize) -> (usize, usize) {
+    let height = height.max(1);
+    if selected >= height {
+        (selected + 1 - height, selected)
+    } else {
+        (0, height - 1)
+    }
+}
+
+fn main_menu_size(rows: usize, cols: usize, header: MenuHeader) -> (usize, usize, usize, usize) {
     // x, y, width, height
     let width = cols;
     let x = 0;
     let y = 0;
-    let height = rows.saturating_sub(RESERVE_ROW_COUNT);
+    let height = rows.saturating_sub(header.rows() + COUNTER_ROW_COUNT);
 
     (x, y, width, height)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn prepare_row_text(
     row: String,
     id: usize,
     max_length: usize,
     selected: bool,
     indices: Vec<usize>,
+    id_match_len: usize,
+    marker: Option<usize>,
     ui_style: &UiStyle,
 ) -> Text {
-    let truncated_row = {
-        let formatted = format!("{}. {}", id, row);
-        if formatted.len() > max_length {
-            let truncated_len = max_length.saturating_sub(3);
-            let mut truncated_str = formatted.chars().take(truncated_len).collect::<String>();
-            truncated_str.push_str("...");
-            truncated_str
-        } else {
-            formatted
-        }
+    let prefix = marker.map_or(String::new(), |_| format!("{} ", ROW_MARKER));
+    let formatted = format!("{}. {}{}", id, prefix, row);
+    // Only the characters before the ellipsis can be highlighted.
+    let (truncated_row, visible_len) = if formatted.width() > max_length {
+        let (mut truncated_str, truncated_len) =
+            take_width(&formatted, max_length.saturating_sub(3));
+        truncated_str.push_str("...");
+        (truncated_str, truncated_len)
+    } else {
+        let len = formatted.chars().count();
+        (formatted, len)
     };
 
     let mut row_text = Text::new(truncated_row);
     if selected && ui_style.selected_item_frame {
         row_text = row_text.selected()
     }
-    let fix_id_shift = id.to_string().len() + 2;
+    let id_len = id.to_string().len();
+    let fix_id_shift = id_len + 2 + prefix.chars().count();
 
-    let new_indices: HashSet<usize> = indices.iter().map(|i| i + fix_id_shift).collect();
+    let mut new_indices: HashSet<usize> = indices.iter().map(|i| i + fix_id_shift).collect();
+    new_indices.extend(0..id_match_len.min(id_len));
 
+    let marker = marker.filter(|_| id_len + 2 < visible_len);
     for i in 0..row_text.len() {
-        if new_indices.contains(&i) {
-            row_text = row_text.color_range(ui_style.match_color, i..i + 1);
+        if let Some(color) = marker.filter(|_| i == id_len + 2) {
+            row_text = row_text.color_range(color, i..i + 1);
+        } else if i < visible_len && new_indices.contains(&i) {
+            row_text = row_text.color_range(ui_style.match_color(), i..i + 1);
         } else if selected {
-            row_text = row_text.color_range(ui_style.active_item_color, i..i + 1);
+            row_text = row_text.color_range(ui_style.active_item_color(), i..i + 1);
         }
     }
 
     row_text
 }
 
+// Soft-wraps the row over as many lines as it needs, up to max_lines.
+// Follow-up lines are indented past the id and the marker.
+#[allow(clippy::too_many_arguments)]
+fn prepare_wrapped_row_text(
+    row: String,
+    id: usize,
+    max_length: usize,
+    max_lines: usize,
+    indices: Vec<usize>,
+    id_match_len: usize,
+    marker: Option<usize>,
+    ui_style: &UiStyle,
+) -> Vec<Text> {
+    let prefix_len =
+        id.to_string().len() + 2 + marker.map_or(0, |_| ROW_MARKER.chars().count() + 1);
+    let mut lines = wrap_text(&row, max_length.saturating_sub(prefix_len)).into_iter();
+    let first = lines.next().unwrap_or_default();
+
+    let first_len = first.chars().count();
+    let indices = indices.into_iter().filter(|&i| i < first_len).collect();
+    let mut texts = vec![prepare_row_text(
+        first,
+        id,
+        max_length,
+        true,
+        indices,
+        id_match_len,
+        marker,
+        ui_style,
+    )];
+
+    for line in lines.take(max_lines.saturating_sub(1)) {
+        let mut text = Text::new(format!("{}{}", " ".repeat(prefix_len), line));
+        if ui_style.selected_item_frame {
+            text = text.selected();
+        }
+        texts.push(text.color_range(ui_style.active_item_color(), ..));
+    }
+
+    texts
+}
+
 pub fn render_mode<T: std::fmt::Display + PartialEq + Copy>(
     x: usize,
     y: usize,
@@ -136,7 +540,7 @@ pub fn render_mode<T: std::fmt::Display + PartialEq + Copy>(
 
     print_text_with_coordinates(
         Text::new(key_indication_text)
-            .color_range(ui_style.match_color, ..)
+            .color_range(ui_style.mode_color(), ..)
             .opaque(),
         x,
         y,
@@ -155,13 +559,157 @@ pub fn render_mode<T: std::fmt::Display + PartialEq + Copy>(
     });
 }
 
-fn render_search_block(x: usize, y: usize, filter: String, filter_by: String, ui_style: &UiStyle) {
-    let filter = format!("Search (by {}): {}_", filter_by, filter.clone());
+// One line of "key action" hints, dropping the hints that don't fit.
+pub fn render_footer(
+    x: usize,
+    y: usize,
+    width: usize,
+    hints: &[(String, &str)],
+    ui_style: &UiStyle,
+) {
+    let (line, keys) = fit_footer(hints, width);
+    let mut text = Text::new(line).opaque();
+    for (start, end) in keys {
+        text = text.color_range(ui_style.match_color(), start..end);
+    }
+    print_text_with_coordinates(text, x, y, None, None);
+}
+
+// Drawn over the top of the list, below the search line, so the list keeps
+// its place while suggestions come and go.
+pub fn render_suggestions(
+    rows: usize,
+    cols: usize,
+    header: MenuHeader,
+    suggestions: &[String],
+    selected: Option<usize>,
+    shown: usize,
+    ui_style: &UiStyle,
+) {
+    let (x, y, width, height) = main_menu_size(rows, cols, header);
+    let shown = shown.min(height).min(suggestions.len());
+    if shown == 0 {
+        return;
+    }
+    let (begin, end) = visible_range(selected.unwrap_or(0), shown);
+    let box_width = suggestions
+        .iter()
+        .map(|suggestion| suggestion.width() + 2)
+        .max()
+        .unwrap_or(0)
+        .min(width.saturating_sub(4));
+
+    for (line, i) in (begin..=end.min(suggestions.len() - 1)).enumerate() {
+        let (suggestion, _) = fit_column(&suggestions[i], box_width.saturating_sub(2));
+        let mut text = Text::new(format!(" {} ", suggestion))
+            .opaque()
+            .color_range(ui_style.match_color(), ..);
+        if selected == Some(i) {
+            text = text.selected();
+        }
+        let row = y + header.search_row() + 1 + line;
+        print_text_with_coordinates(text, x + 4, row, None, None);
+    }
+}
+
+// A framed box over the list, below the search line, with the lines wrapped to
+// its width and cut at its height.
+pub fn render_popup(
+    rows: usize,
+    cols: usize,
+    header: MenuHeader,
+    title: &str,
+    lines: &[String],
+    ui_style: &UiStyle,
+) {
+    let (x, y, width, height) = main_menu_size(rows, cols, header);
+    for (i, line) in popup_lines(title, lines, width.saturating_sub(4), height)
+        .into_iter()
+        .enumerate()
+    {
+        let frame_end = line.chars().count();
+        let text = Text::new(line)
+            .opaque()
+            .color_range(ui_style.chrome_color, ..1)
+            .color_range(ui_style.chrome_color, frame_end.saturating_sub(1)..);
+        let text = if i == 0 {
+            text.color_range(ui_style.chrome_color, ..)
+        } else {
+            text
+        };
+        print_text_with_coordinates(text, x + 2, y + header.rows() + i, None, None);
+    }
+}
+
+fn popup_lines(title: &str, lines: &[String], width: usize, height: usize) -> Vec<String> {
+    if width < 6 || height < 3 {
+        return Vec::new();
+    }
+    let inner = width - 4;
+    let (title, _) = fit_column(title, inner.saturating_sub(2));
+    let title = title.trim_end();
+    let mut framed = vec![format!(
+        "┌─ {} {}┐",
+        title,
+        "─".repeat(inner - 1 - title.width())
+    )];
+    let body = lines
+        .iter()
+        .flat_map(|line| match line.is_empty() {
+            // Blank lines separate the sections.
+            true => vec![String::new()],
+            false => wrap_text(line, inner),
+        })
+        .take(height - 2);
+    for line in body {
+        let (line, _) = fit_column(&line, inner);
+        framed.push(format!("│ {} │", line));
+    }
+    framed.push(format!("└{}┘", "─".repeat(width - 2)));
+    framed
+}
+
+// The footer line and the char ranges of its keys.
+fn fit_footer(hints: &[(String, &str)], width: usize) -> (String, Vec<(usize, usize)>) {
+    let mut line = String::new();
+    let mut keys = Vec::new();
+    for (key, action) in hints {
+        let separator = if line.is_empty() { "" } else { "  " };
+        let hint = format!("{}{} {}", separator, key, action);
+        if line.width() + hint.width() > width {
+            break;
+        }
+        let start = line.chars().count() + separator.len();
+        keys.push((start, start + key.chars().count()));
+        line.push_str(&hint);
+    }
+    (line, keys)
+}
+
+fn render_search_block(
+    x: usize,
+    y: usize,
+    filter: String,
+    filter_by: String,
+    count: usize,
+    total: usize,
+    ui_style: &UiStyle,
+) {
+    let filter = search_line(&filter, &filter_by, count, total);
 
     let text = Text::new(filter).color_range(ui_style.chrome_color, ..6);
     print_text_with_coordinates(text, x, y, None, None);
 }
 
+// The match count is only worth showing while a filter narrows the list.
+fn search_line(filter: &str, filter_by: &str, count: usize, total: usize) -> String {
+    let mut line = format!("Search (by {}): {}_", filter_by, filter);
+    if !filter.is_empty() {
+        line.push_str(&format!("  {}/{} matches", count, total));
+    }
+    line
+}
+
 // Render row with All row-counter
 fn render_all_counter(x: usize, y: usize, all: usize, ui_style: &UiStyle) {
     let all_count = format!("All: {}", all);
@@ -185,11 +733,239 @@ fn render_right_counter_with_max(
 
 // Render row with right counter
 fn render_right_counter(count: usize, width: usize, y: usize, ui_style: &UiStyle) {
-    if count == 0 {
+    let Some((x, row)) = right_counter(count, width) else {
         return;
-    }
-    let row = format!("+ {} more  ", count);
-    let x = width - row.len();
+    };
     let text = Text::new(row).color_range(ui_style.chrome_color, ..);
     print_text_with_coordinates(text, x, y, None, None);
 }
+
+// The counter and where it starts, right-aligned. Nothing is shown when it
+// doesn't fit, which only happens on menus narrower than RESERVE_COLUMN_COUNT.
+fn right_counter(count: usize, width: usize) -> Option<(usize, String)> {
+    if count == 0 {
+        return None;
+    }
+    let row = format!("+ {} more  ", count);
+    let x = width.checked_sub(row.len())?;
+    Some((x, row))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        fit_column, fit_footer, menu_height, popup_lines, right_counter, search_line, take_width,
+        visible_range, visible_rows, wrap_text, MenuHeader, ThemeColors, UiStyle,
+        RESERVE_COLUMN_COUNT,
+    };
+    use zellij_tile::prelude::{PaletteColor, StyleDeclaration, Styling};
+
+    #[test]
+    fn visible_range_keeps_the_selection_in_the_window() {
+        assert_eq!(visible_range(2, 5), (0, 4));
+        assert_eq!(visible_range(6, 5), (2, 6));
+    }
+
+    #[test]
+    fn right_counter_is_skipped_when_it_does_not_fit() {
+        assert_eq!(right_counter(0, 40), None);
+        assert_eq!(right_counter(3, 40), Some((30, "+ 3 more  ".to_string())));
+        assert_eq!(right_counter(3, 10), Some((0, "+ 3 more  ".to_string())));
+        assert_eq!(right_counter(3, 9), None);
+        assert_eq!(right_counter(120, 5), None);
+        // Menus are only drawn this wide, which fits any realistic count.
+        assert!(right_counter(usize::MAX, RESERVE_COLUMN_COUNT).is_some());
+    }
+
+    #[test]
+    fn menu_height_leaves_only_the_drawn_rows() {
+        let plain = MenuHeader::default();
+        let notice = MenuHeader { notice: true };
+        assert_eq!(menu_height(20, plain), 16);
+        assert_eq!(menu_height(20, notice), 15);
+        assert_eq!(menu_height(plain.reserve_rows(), plain), 1);
+        assert_eq!(menu_height(notice.reserve_rows(), notice), 1);
+        assert_eq!(plain.reserve_rows(), 5);
+        assert_eq!(menu_height(4, plain), 0);
+        assert_eq!(menu_height(3, notice), 0);
+    }
+
+    #[test]
+    fn popup_lines_frame_the_wrapped_lines() {
+        let lines = vec!["deploy the app".to_string(), String::new()];
+        assert_eq!(
+            popup_lines("build", &lines, 14, 10),
+            vec![
+                "┌─ build ────┐",
+                "│ deploy the │",
+                "│ app        │",
+                "│            │",
+                "└────────────┘",
+            ]
+        );
+        assert_eq!(popup_lines("build", &lines, 14, 3).len(), 3);
+        assert!(popup_lines("build", &lines, 14, 2).is_empty());
+        assert!(popup_lines("build", &lines, 4, 10).is_empty());
+        assert_eq!(popup_lines("build", &lines, 6, 3)[0], "┌─  ─┐");
+    }
+
+    #[test]
+    fn visible_rows_keeps_the_selection_and_its_headers_in_the_window() {
+        assert_eq!(visible_rows(&[1, 1, 1, 1], 1, 5), (0, 3));
+        assert_eq!(visible_rows(&[1, 1, 1, 1, 1, 1], 5, 3), (3, 5));
+        assert_eq!(visible_rows(&[2, 1, 2, 1], 3, 4), (1, 3));
+        assert_eq!(visible_rows(&[2, 1, 2, 1], 0, 4), (0, 1));
+        assert_eq!(visible_rows(&[1, 3, 1], 1, 2), (1, 1));
+        assert_eq!(visible_rows(&[], 0, 4), (0, 0));
+    }
+
+    #[test]
+    fn fit_footer_drops_hints_that_do_not_fit() {
+        let hints = [
+            ("Enter".to_string(), "run"),
+            ("Ctrl e".to_string(), "edit"),
+            ("Ctrl r".to_string(), "reload"),
+        ];
+        assert_eq!(
+            fit_footer(&hints, 80),
+            (
+                "Enter run  Ctrl e edit  Ctrl r reload".to_string(),
+                vec![(0, 5), (11, 17), (24, 30)]
+            )
+        );
+        assert_eq!(fit_footer(&hints, 22).0, "Enter run  Ctrl e edit");
+        assert_eq!(fit_footer(&hints, 4).0, "");
+        assert_eq!(fit_footer(&[("Enter".to_string(), "実行")], 9).0, "");
+    }
+
+    #[test]
+    fn search_line_shows_the_match_count_while_filtering() {
+        assert_eq!(search_line("", "Name", 12, 12), "Search (by Name): _");
+        assert_eq!(
+            search_line("dep", "Name", 3, 12),
+            "Search (by Name): dep_  3/12 matches"
+        );
+    }
+
+    #[test]
+    fn fit_column_pads_or_truncates_to_the_width() {
+        assert_eq!(fit_column("build", 8), ("build   ".to_string(), 5));
+        assert_eq!(
+            fit_column("deploy-production", 8),
+            ("deplo...".to_string(), 5)
+        );
+    }
+
+    #[test]
+    fn take_width_counts_wide_characters_twice() {
+        assert_eq!(take_width("build", 3), ("bui".to_string(), 3));
+        assert_eq!(take_width("部署生产", 5), ("部署".to_string(), 2));
+        assert_eq!(take_width("🚀 deploy", 4), ("🚀 d".to_string(), 3));
+        assert_eq!(take_width("ok", 10), ("ok".to_string(), 2));
+    }
+
+    #[test]
+    fn fit_column_measures_cjk_and_emoji_by_width() {
+        assert_eq!(fit_column("部署", 6), ("部署  ".to_string(), 2));
+        assert_eq!(fit_column("部署生产环境", 8), ("部署 ...".to_string(), 2));
+        assert_eq!(fit_column("🚀🚀🚀🚀🚀", 8), ("🚀🚀 ...".to_string(), 2));
+        assert_eq!(fit_column("🚀 go", 8), ("🚀 go   ".to_string(), 4));
+    }
+
+    #[test]
+    fn wrap_text_breaks_on_word_boundaries() {
+        assert_eq!(
+            wrap_text("deploy the app to production", 10),
+            vec!["deploy the", "app to", "production"]
+        );
+    }
+
+    #[test]
+    fn wrap_text_keeps_explicit_line_breaks_and_splits_long_words() {
+        assert_eq!(
+            wrap_text("first\nabcdefghijkl", 5),
+            vec!["first", "abcde", "fghij", "kl"]
+        );
+    }
+
+    #[test]
+    fn wrap_text_and_popups_measure_cjk_and_emoji_by_width() {
+        assert_eq!(
+            wrap_text("部署 生产环境 🚀 ok", 6),
+            vec!["部署", "生产环", "境 🚀", "ok"]
+        );
+        assert_eq!(wrap_text("部a", 1), vec!["部", "a"]);
+        assert_eq!(
+            popup_lines("部署", &["生产 ok".to_string()], 14, 10),
+            vec!["┌─ 部署 ─────┐", "│ 生产 ok    │", "└────────────┘",]
+        );
+    }
+
+    #[test]
+    fn theme_colors_pick_the_emphasis_that_stands_out_on_the_background() {
+        let dark = PaletteColor::Rgb((30, 30, 30));
+        let light = PaletteColor::Rgb((240, 240, 240));
+        let style = |background, bright_emphasis: usize| {
+            let mut style = StyleDeclaration {
+                background,
+                emphasis_0: PaletteColor::Rgb((128, 128, 128)),
+                emphasis_1: PaletteColor::Rgb((128, 128, 128)),
+                emphasis_2: PaletteColor::Rgb((128, 128, 128)),
+                emphasis_3: PaletteColor::Rgb((128, 128, 128)),
+                ..Default::default()
+            };
+            let bright = if background == dark { light } else { dark };
+            match bright_emphasis {
+                0 => style.emphasis_0 = bright,
+                1 => style.emphasis_1 = bright,
+                2 => style.emphasis_2 = bright,
+                _ => style.emphasis_3 = bright,
+            }
+            style
+        };
+        let colors = Styling {
+            text_selected: style(light, 1),
+            text_unselected: style(dark, 2),
+            ..Default::default()
+        };
+
+        let theme = ThemeColors::from_styling(&colors);
+        assert_eq!(
+            theme,
+            Some(ThemeColors {
+                selected: 1,
+                unselected: 2,
+            })
+        );
+
+        let mut ui_style = UiStyle {
+            theme,
+            ..Default::default()
+        };
+        assert_eq!(ui_style.active_item_color(), 1);
+        assert_eq!(ui_style.mode_color(), 2);
+        assert_eq!(ui_style.match_color(), 3);
+
+        // Colors from the config win over the theme.
+        ui_style.match_color = Some(0);
+        ui_style.active_item_color = Some(3);
+        assert_eq!(ui_style.active_item_color(), 3);
+        assert_eq!(ui_style.mode_color(), 0);
+    }
+
+    #[test]
+    fn theme_colors_fall_back_without_rgb_colors() {
+        let colors = Styling {
+            text_selected: StyleDeclaration {
+                background: PaletteColor::EightBit(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(ThemeColors::from_styling(&colors), None);
+
+        let ui_style = UiStyle::default();
+        assert_eq!(ui_style.active_item_color(), 0);
+        assert_eq!(ui_style.mode_color(), 3);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::core::{IdGetter, LabelsGetter, NameGetter};
+use crate::core::{DescGetter, IdGetter, LabelsGetter, NameGetter};
 use std::path::Path;
 
 #[derive(Default, Debug, Clone)]
@@ -43,7 +43,13 @@ impl NameGetter for EditableFile {
 
 impl LabelsGetter for EditableFile {
     fn get_labels(&self) -> Vec<String> {
-        panic!("unsupported")
+        Vec::new()
+    }
+}
+
+impl DescGetter for EditableFile {
+    fn get_desc(&self) -> String {
+        String::new()
     }
 }
 
@@ -0,0 +1,74 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+use std::collections::HashMap;
+
+// Parses the NUL-separated output of `env -0`.
+pub(crate) fn parse_env(output: &[u8]) -> HashMap<String, String> {
+    String::from_utf8_lossy(output)
+        .split('\0')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+// `{{env "NAME"}}` expands to the value of an environment variable captured at load time.
+pub(crate) struct EnvHelper {
+    pub env: HashMap<String, String>,
+}
+
+impl HelperDef for EnvHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let name = h
+            .param(0)
+            .and_then(|param| param.value().as_str())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("env", 0))?;
+        let value = self.env.get(name).ok_or_else(|| {
+            RenderErrorReason::Other(format!("Environment variable '{}' is not set", name))
+        })?;
+        out.write(value)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_env, EnvHelper};
+    use handlebars::Handlebars;
+    use std::collections::HashMap;
+
+    #[test]
+    fn parse_env_splits_entries_and_keeps_values_with_separators() {
+        let env = parse_env(b"HOME=/home/user\0OPTS=a=b\nc\0INVALID\0");
+
+        assert_eq!(env.len(), 2);
+        assert_eq!(env.get("HOME").unwrap(), "/home/user");
+        assert_eq!(env.get("OPTS").unwrap(), "a=b\nc");
+    }
+
+    #[test]
+    fn env_helper_expands_known_vars_and_rejects_unknown_ones() {
+        let env = HashMap::from([("HOME".to_string(), "/home/user".to_string())]);
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("env", Box::new(EnvHelper { env }));
+
+        let rendered = handlebars
+            .render_template("cd {{env \"HOME\"}}", &())
+            .unwrap();
+        assert_eq!(rendered, "cd /home/user");
+
+        let err = handlebars
+            .render_template("cd {{env \"MISSING\"}}", &())
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Environment variable 'MISSING' is not set"));
+    }
+}
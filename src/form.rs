@@ -0,0 +1,130 @@
+use crate::bookmark::Bookmark;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FormField {
+    Name,
+    Desc,
+    Cmds,
+}
+
+// Input state for creating a bookmark from inside the plugin.
+#[derive(Debug, Clone)]
+pub(crate) struct BookmarkForm {
+    pub name: String,
+    pub desc: String,
+    pub cmds: Vec<String>,
+    pub field: FormField,
+    pub input: String,
+    pub error: Option<String>,
+}
+
+impl BookmarkForm {
+    pub(crate) fn new() -> Self {
+        Self {
+            name: String::new(),
+            desc: String::new(),
+            cmds: Vec::new(),
+            field: FormField::Name,
+            input: String::new(),
+            error: None,
+        }
+    }
+
+    // Commits the input to the current field. Command lines are collected until
+    // an empty one is submitted. Returns true once the form is complete.
+    pub(crate) fn submit(&mut self, existing_names: &HashSet<String>) -> bool {
+        self.error = None;
+        let input = self.input.trim().to_string();
+
+        match self.field {
+            FormField::Name => {
+                if input.is_empty() {
+                    self.error = Some("Name must not be empty".to_string());
+                } else if existing_names.contains(&input) {
+                    self.error = Some(format!("Bookmark '{}' already exists", input));
+                } else {
+                    self.name = input;
+                    self.field = FormField::Desc;
+                    self.input = self.desc.clone();
+                }
+            }
+            FormField::Desc => {
+                self.desc = input;
+                self.field = FormField::Cmds;
+                self.input.clear();
+            }
+            FormField::Cmds => {
+                if !input.is_empty() {
+                    self.cmds.push(input);
+                    self.input.clear();
+                } else if self.cmds.is_empty() {
+                    self.error = Some("At least one command is required".to_string());
+                } else {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    pub(crate) fn to_bookmark(&self) -> Bookmark {
+        Bookmark {
+            name: self.name.clone(),
+            desc: self.desc.clone(),
+            cmds: self.cmds.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BookmarkForm, FormField};
+    use std::collections::HashSet;
+
+    fn submit(form: &mut BookmarkForm, input: &str, names: &HashSet<String>) -> bool {
+        form.input = input.to_string();
+        form.submit(names)
+    }
+
+    #[test]
+    fn submit_walks_through_fields_and_builds_bookmark() {
+        let names = HashSet::new();
+        let mut form = BookmarkForm::new();
+
+        assert!(!submit(&mut form, "deploy", &names));
+        assert_eq!(form.field, FormField::Desc);
+        assert!(!submit(&mut form, "Deploy the app", &names));
+        assert!(!submit(&mut form, "make build", &names));
+        assert!(!submit(&mut form, "make deploy", &names));
+        assert!(submit(&mut form, "", &names));
+
+        let bookmark = form.to_bookmark();
+        assert_eq!(bookmark.name, "deploy");
+        assert_eq!(bookmark.desc, "Deploy the app");
+        assert_eq!(bookmark.cmds, vec!["make build", "make deploy"]);
+    }
+
+    #[test]
+    fn submit_rejects_duplicate_names_and_missing_commands() {
+        let names = HashSet::from(["deploy".to_string()]);
+        let mut form = BookmarkForm::new();
+
+        assert!(!submit(&mut form, "deploy", &names));
+        assert_eq!(form.field, FormField::Name);
+        assert_eq!(
+            form.error.as_deref(),
+            Some("Bookmark 'deploy' already exists")
+        );
+
+        submit(&mut form, "release", &names);
+        submit(&mut form, "", &names);
+        assert!(!submit(&mut form, "", &names));
+        assert_eq!(
+            form.error.as_deref(),
+            Some("At least one command is required")
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub(crate) struct HistoryEntry {
+    pub name: String,
+    pub last_used: u64,
+    pub count: usize,
+    // Exit code of the last run in a command pane.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+// Persisted history of executed bookmarks, most recently used first.
+#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct History {
+    #[serde(default)]
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    pub(crate) fn record(&mut self, name: &str, now: u64, limit: usize) {
+        let mut entry = match self.entries.iter().position(|e| e.name == name) {
+            Some(i) => self.entries.remove(i),
+            None => HistoryEntry {
+                name: name.to_string(),
+                ..Default::default()
+            },
+        };
+        entry.last_used = now;
+        entry.count += 1;
+
+        self.entries.insert(0, entry);
+        self.entries.truncate(limit);
+    }
+
+    // Runs dropped from the history keep no status.
+    pub(crate) fn record_exit(&mut self, name: &str, exit_code: i32) -> bool {
+        match self.entries.iter_mut().find(|e| e.name == name) {
+            Some(entry) => {
+                entry.exit_code = Some(exit_code);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn exit_code(&self, name: &str) -> Option<i32> {
+        self.entries
+            .iter()
+            .find(|e| e.name == name)
+            .and_then(|e| e.exit_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::History;
+
+    #[test]
+    fn record_moves_entry_to_front_and_counts_runs() {
+        let mut history = History::default();
+
+        history.record("build", 1, 10);
+        history.record("test", 2, 10);
+        history.record("build", 3, 10);
+
+        let names: Vec<&str> = history.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["build", "test"]);
+        assert_eq!(history.entries[0].count, 2);
+        assert_eq!(history.entries[0].last_used, 3);
+        assert_eq!(history.entries[1].count, 1);
+    }
+
+    #[test]
+    fn record_caps_the_history() {
+        let mut history = History::default();
+
+        history.record("a", 1, 2);
+        history.record("b", 2, 2);
+        history.record("c", 3, 2);
+
+        let names: Vec<&str> = history.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["c", "b"]);
+    }
+
+    #[test]
+    fn record_exit_keeps_the_last_exit_code_of_known_runs() {
+        let mut history = History::default();
+        history.record("deploy", 1, 10);
+
+        assert!(history.record_exit("deploy", 0));
+        assert!(history.record_exit("deploy", 2));
+        assert!(!history.record_exit("build", 1));
+        assert_eq!(history.exit_code("deploy"), Some(2));
+        assert_eq!(history.exit_code("build"), None);
+
+        history.record("deploy", 2, 10);
+        assert_eq!(history.exit_code("deploy"), Some(2));
+    }
+}
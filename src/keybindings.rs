@@ -7,6 +7,35 @@ const BIND_RELOAD: &str = "bind_reload";
 const BIND_SWITCH_FILTER_LABEL: &str = "bind_switch_filter_label";
 const BIND_SWITCH_FILTER_ID: &str = "bind_switch_filter_id";
 const BIND_DESCRIBE: &str = "bind_describe";
+const BIND_SWITCH_FILTER_FUZZY: &str = "bind_switch_filter_fuzzy";
+const BIND_SWITCH_FILTER_REGEX: &str = "bind_switch_filter_regex";
+const BIND_RUN_IN_PANE: &str = "bind_run_in_pane";
+const BIND_CREATE: &str = "bind_create";
+const BIND_DELETE: &str = "bind_delete";
+const BIND_PREVIEW: &str = "bind_preview";
+const BIND_VALIDATE: &str = "bind_validate";
+const BIND_SWITCH_FILTER_ALL: &str = "bind_switch_filter_all";
+const BIND_UP: &str = "bind_up";
+const BIND_DOWN: &str = "bind_down";
+const BIND_EXIT: &str = "bind_exit";
+const BIND_RUN: &str = "bind_run";
+const BIND_NEXT_MODE: &str = "bind_next_mode";
+const BIND_PREV_MODE: &str = "bind_prev_mode";
+const BIND_COPY: &str = "bind_copy";
+const BIND_EXPORT: &str = "bind_export";
+const BIND_IMPORT: &str = "bind_import";
+const BIND_APPEND_ARGS: &str = "bind_append_args";
+const BIND_EDIT_BOOKMARK: &str = "bind_edit_bookmark";
+const BIND_SORT: &str = "bind_sort";
+const BIND_PIN: &str = "bind_pin";
+const BIND_DUMP_COMMANDS: &str = "bind_dump_commands";
+const BIND_CLEAR: &str = "bind_clear";
+const BIND_TOGGLE_CASE: &str = "bind_toggle_case";
+const BIND_MARK: &str = "bind_mark";
+const BIND_DUPLICATE: &str = "bind_duplicate";
+const BIND_RELOAD_SOFT: &str = "bind_reload_soft";
+const BIND_VIEW_CMDS: &str = "bind_view_cmds";
+const BIND_SHORTCUT: &str = "bind_shortcut";
 
 #[derive(Clone)]
 pub(crate) struct Keybindings {
@@ -15,6 +44,35 @@ pub(crate) struct Keybindings {
     pub switch_filter_label: Keybinding,
     pub switch_filter_id: Keybinding,
     pub describe: Keybinding,
+    pub switch_filter_fuzzy: Keybinding,
+    pub switch_filter_regex: Keybinding,
+    pub run_in_pane: Keybinding,
+    pub create: Keybinding,
+    pub delete: Keybinding,
+    pub preview: Keybinding,
+    pub validate: Keybinding,
+    pub switch_filter_all: Keybinding,
+    pub up: Keybinding,
+    pub down: Keybinding,
+    pub exit: Keybinding,
+    pub run: Keybinding,
+    pub next_mode: Keybinding,
+    pub prev_mode: Keybinding,
+    pub copy: Keybinding,
+    pub export: Keybinding,
+    pub import: Keybinding,
+    pub append_args: Keybinding,
+    pub edit_bookmark: Keybinding,
+    pub sort: Keybinding,
+    pub pin: Keybinding,
+    pub dump_commands: Keybinding,
+    pub clear: Keybinding,
+    pub toggle_case: Keybinding,
+    pub mark: Keybinding,
+    pub duplicate: Keybinding,
+    pub reload_soft: Keybinding,
+    pub view_cmds: Keybinding,
+    pub shortcut: Keybinding,
 }
 
 impl Default for Keybindings {
@@ -25,6 +83,35 @@ impl Default for Keybindings {
             switch_filter_label: Keybinding::new(KeyModifier::Ctrl, 'l'),
             switch_filter_id: Keybinding::new(KeyModifier::Ctrl, 'i'),
             describe: Keybinding::new(KeyModifier::Ctrl, 'd'),
+            switch_filter_fuzzy: Keybinding::new(KeyModifier::Ctrl, 'f'),
+            switch_filter_regex: Keybinding::new(KeyModifier::Ctrl, 'x'),
+            run_in_pane: Keybinding::new(KeyModifier::Ctrl, 'w'),
+            create: Keybinding::new(KeyModifier::Ctrl, 'a'),
+            delete: Keybinding::new(KeyModifier::Ctrl, 'k'),
+            preview: Keybinding::new(KeyModifier::Ctrl, 'v'),
+            validate: Keybinding::new(KeyModifier::Ctrl, 'y'),
+            switch_filter_all: Keybinding::new(KeyModifier::Ctrl, 'u'),
+            up: Keybinding::from_bare_key(BareKey::Up),
+            down: Keybinding::from_bare_key(BareKey::Down),
+            exit: Keybinding::from_bare_key(BareKey::Esc),
+            run: Keybinding::from_bare_key(BareKey::Enter),
+            next_mode: Keybinding::from_bare_key(BareKey::Right),
+            prev_mode: Keybinding::from_bare_key(BareKey::Left),
+            copy: Keybinding::new(KeyModifier::Alt, 'c'),
+            export: Keybinding::new(KeyModifier::Alt, 'e'),
+            import: Keybinding::new(KeyModifier::Alt, 'm'),
+            append_args: Keybinding::new(KeyModifier::Alt, 'a'),
+            edit_bookmark: Keybinding::new(KeyModifier::Alt, 'b'),
+            sort: Keybinding::new(KeyModifier::Alt, 's'),
+            pin: Keybinding::new(KeyModifier::Alt, 't'),
+            dump_commands: Keybinding::new(KeyModifier::Alt, 'd'),
+            clear: Keybinding::new(KeyModifier::Alt, 'u'),
+            toggle_case: Keybinding::new(KeyModifier::Alt, 'g'),
+            mark: Keybinding::new(KeyModifier::Alt, 'x'),
+            duplicate: Keybinding::new(KeyModifier::Alt, 'y'),
+            reload_soft: Keybinding::new(KeyModifier::Alt, 'r'),
+            view_cmds: Keybinding::new(KeyModifier::Alt, 'v'),
+            shortcut: Keybinding::new(KeyModifier::Alt, 'q'),
         }
     }
 }
@@ -48,6 +135,93 @@ impl Keybindings {
         if let Some(value) = conf.get(BIND_DESCRIBE) {
             default.describe = parse_key_info(value)?
         }
+        if let Some(value) = conf.get(BIND_SWITCH_FILTER_FUZZY) {
+            default.switch_filter_fuzzy = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_SWITCH_FILTER_REGEX) {
+            default.switch_filter_regex = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_RUN_IN_PANE) {
+            default.run_in_pane = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_CREATE) {
+            default.create = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_DELETE) {
+            default.delete = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_PREVIEW) {
+            default.preview = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_VALIDATE) {
+            default.validate = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_SWITCH_FILTER_ALL) {
+            default.switch_filter_all = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_UP) {
+            default.up = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_DOWN) {
+            default.down = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_EXIT) {
+            default.exit = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_RUN) {
+            default.run = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_NEXT_MODE) {
+            default.next_mode = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_PREV_MODE) {
+            default.prev_mode = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_COPY) {
+            default.copy = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_EXPORT) {
+            default.export = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_IMPORT) {
+            default.import = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_APPEND_ARGS) {
+            default.append_args = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_EDIT_BOOKMARK) {
+            default.edit_bookmark = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_SORT) {
+            default.sort = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_PIN) {
+            default.pin = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_DUMP_COMMANDS) {
+            default.dump_commands = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_CLEAR) {
+            default.clear = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_TOGGLE_CASE) {
+            default.toggle_case = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_MARK) {
+            default.mark = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_DUPLICATE) {
+            default.duplicate = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_RELOAD_SOFT) {
+            default.reload_soft = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_VIEW_CMDS) {
+            default.view_cmds = parse_key_info(value)?
+        }
+        if let Some(value) = conf.get(BIND_SHORTCUT) {
+            default.shortcut = parse_key_info(value)?
+        }
         Ok(default)
     }
 }
@@ -1,15 +1,51 @@
-use crate::core::{IdGetter, LabelsGetter, NameGetter};
+use crate::core::{DescGetter, IdGetter, LabelsGetter, NameGetter};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+// Number of emphasis colors a Text can be tinted with.
+const LABEL_COLOR_COUNT: u64 = 4;
+pub(crate) const MANAGED_LABEL_PREFIX: &str = "file::";
 
 #[derive(Default, Debug, Clone)]
 pub(crate) struct Label {
     pub id: usize,
     pub name: String,
+    // Number of bookmarks carrying the label in the loaded config.
+    pub count: usize,
 }
 
 impl Label {
     pub(crate) fn new(id: usize, name: String) -> Self {
-        Self { id, name }
+        Self { id, name, count: 0 }
+    }
+}
+
+// The labels a hierarchical label is nested under, outermost first, e.g.
+// `infra` and `infra/aws` for `infra/aws/prod`. Managed file labels are flat.
+pub(crate) fn label_ancestors(label: &str, separator: &str) -> Vec<String> {
+    if separator.is_empty() || label.starts_with(MANAGED_LABEL_PREFIX) {
+        return Vec::new();
+    }
+    label
+        .match_indices(separator)
+        .map(|(i, _)| label[..i].to_string())
+        .filter(|ancestor| !ancestor.is_empty())
+        .collect()
+}
+
+// The configured color of the label, or one derived from its name.
+// Managed file labels are not colored.
+pub(crate) fn label_color(label: &str, colors: &HashMap<String, usize>) -> Option<usize> {
+    if label.starts_with(MANAGED_LABEL_PREFIX) {
+        return None;
+    }
+    if let Some(color) = colors.get(label) {
+        return Some(*color);
     }
+
+    let mut hasher = DefaultHasher::new();
+    label.hash(&mut hasher);
+    Some((hasher.finish() % LABEL_COLOR_COUNT) as usize)
 }
 
 impl NameGetter for Label {
@@ -26,6 +62,40 @@ impl IdGetter for Label {
 
 impl LabelsGetter for Label {
     fn get_labels(&self) -> Vec<String> {
-        panic!("unsupported")
+        Vec::new()
+    }
+}
+
+impl DescGetter for Label {
+    fn get_desc(&self) -> String {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{label_ancestors, label_color};
+    use std::collections::HashMap;
+
+    #[test]
+    fn label_color_prefers_configured_colors() {
+        let colors = HashMap::from([(String::from("docker"), 2)]);
+
+        assert_eq!(label_color("docker", &colors), Some(2));
+        assert_eq!(label_color("file::main", &colors), None);
+
+        let fallback = label_color("git", &colors);
+        assert!(fallback.is_some_and(|color| color < 4));
+        assert_eq!(label_color("git", &HashMap::new()), fallback);
+    }
+
+    #[test]
+    fn label_ancestors_lists_outer_labels_first() {
+        assert_eq!(
+            label_ancestors("infra/aws/prod", "/"),
+            ["infra", "infra/aws"]
+        );
+        assert!(label_ancestors("infra", "/").is_empty());
+        assert!(label_ancestors("file::conf/main.yaml", "/").is_empty());
     }
 }
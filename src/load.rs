@@ -1,37 +1,221 @@
 use crate::bookmark::Bookmark;
-use crate::config::Config;
-use crate::core::{FilteredList, NameGetter};
+use crate::config::{assign_ids, Config, ConfigFormat};
+use crate::core::{FilterMode, FilteredList, IdMatch, NameGetter};
 use crate::editable_file::EditableFile;
+use crate::history::History;
 use crate::keybindings::Keybindings;
-use crate::label::Label;
-use std::collections::{BTreeMap, HashSet};
+use crate::label::{label_ancestors, Label};
+use crate::pins::Pins;
+use crate::session::Session;
+use crate::workflow::Workflow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::io::{Read, Write};
-use std::path::Path;
-use std::{fs, io};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{fmt, fs, io};
 use zellij_tile::prelude::*;
 
 const CONFIGURATION_EXEC: &str = "exec";
+const CONFIGURATION_DRY_RUN: &str = "dry_run";
 const CONFIGURATION_SEPARATOR: &str = "separator";
+const CONFIGURATION_COMMAND_SEPARATOR: &str = "command_separator";
+const CONFIGURATION_MULTILINE: &str = "multiline";
+const CONFIGURATION_SHELL: &str = "shell";
+const CONFIGURATION_QUOTE_VARS: &str = "quote_vars";
 const CONFIGURATION_CHROME_COLOR: &str = "chrome_color";
 const CONFIGURATION_MATCH_COLOR: &str = "match_color";
 const CONFIGURATION_ACTIVE_ITEM_COLOR: &str = "active_item_color";
 const CONFIGURATION_SELECTED_ITEM_FRAME: &str = "selected_item_frame";
 const CONFIGURATION_IGNORE_CASE: &str = "ignore_case";
+const CONFIGURATION_LABEL_MATCH: &str = "label_match";
+const CONFIGURATION_HIERARCHICAL_LABELS: &str = "hierarchical_labels";
+const CONFIGURATION_LABEL_SEPARATOR: &str = "label_separator";
+const CONFIGURATION_SEARCH_DESCRIPTION: &str = "search_description";
+const CONFIGURATION_WRAP_DESCRIPTION: &str = "wrap_description";
+const CONFIGURATION_DESCRIBE_POPUP: &str = "describe_popup";
+const CONFIGURATION_GROUP_VIEW: &str = "group_view";
+const CONFIGURATION_VIM_KEYS: &str = "vim_keys";
+const CONFIGURATION_WRAP_NAVIGATION: &str = "wrap_navigation";
+const CONFIGURATION_SHOW_FOOTER: &str = "show_footer";
+const CONFIGURATION_CLOSE_AFTER_RUN: &str = "close_after_run";
+const CONFIGURATION_EDIT_DUPLICATE: &str = "edit_duplicate";
+const CONFIGURATION_PRESERVE_FILTER_ON_MODE_SWITCH: &str = "preserve_filter_on_mode_switch";
+const CONFIGURATION_AUTO_RELOAD: &str = "auto_reload";
 const CONFIGURATION_FUZZY_SEARCH: &str = "fuzzy_search";
+const CONFIGURATION_ACRONYM_SEARCH: &str = "acronym_search";
+const CONFIGURATION_ID_MATCH: &str = "id_match";
 const CONFIGURATION_AUTODETECT_FILTER_MODE: &str = "autodetect_filter_mode";
 const CONFIGURATION_FILENAME: &str = "filename";
 const CONFIGURATION_DIRNAME: &str = "dirname";
 const CONFIGURATION_SORT: &str = "sort";
+const CONFIGURATION_SORT_BY: &str = "sort_by";
+const CONFIGURATION_MAX_RESULTS: &str = "max_results";
+const CONFIGURATION_HISTORY_FILENAME: &str = "history_filename";
+const CONFIGURATION_EXPORT_FILENAME: &str = "export_filename";
+const CONFIGURATION_IMPORT_FILENAME: &str = "import_filename";
+const CONFIGURATION_COMMANDS_FILENAME: &str = "commands_filename";
+const CONFIGURATION_RECENT_LIMIT: &str = "recent_limit";
+const CONFIGURATION_MAX_TEMPLATE_DEPTH: &str = "max_template_depth";
+const CONFIGURATION_SESSION_FILENAME: &str = "session_filename";
+const CONFIGURATION_PINS_FILENAME: &str = "pins_filename";
+const CONFIGURATION_INITIAL_MODE: &str = "initial_mode";
+const CONFIGURATION_INITIAL_FILTER: &str = "initial_filter";
+const CONFIGURATION_INITIAL_FILTER_MODE: &str = "initial_filter_mode";
 
-use super::State;
+use super::{Mode, State};
 
 fn sort_by_name<T: NameGetter>(items: &mut [T]) {
     items.sort_by_key(|item| item.get_name());
 }
 
-fn reindex_bookmarks(bookmarks: &mut [Bookmark]) {
-    for (i, bookmark) in bookmarks.iter_mut().enumerate() {
-        bookmark.id = i + 1;
+// Order of bookmarks in Bookmarks mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SortBy {
+    Config,
+    Name,
+    Frequency,
+    Recent,
+}
+
+// Order the lists are re-sorted in at runtime. Id order is the loaded order,
+// whatever ids are fixed in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) enum ListOrder {
+    #[default]
+    Id,
+    Name,
+    Label,
+}
+
+impl ListOrder {
+    // Labels can't be ordered by their labels, so that order is skipped for them.
+    pub(crate) fn next(self, by_label: bool) -> Self {
+        match self {
+            Self::Id => Self::Name,
+            Self::Name if by_label => Self::Label,
+            Self::Name | Self::Label => Self::Id,
+        }
+    }
+}
+
+impl fmt::Display for ListOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id => write!(f, "id"),
+            Self::Name => write!(f, "name"),
+            Self::Label => write!(f, "label"),
+        }
+    }
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "config" => Ok(Self::Config),
+            "name" => Ok(Self::Name),
+            "frequency" => Ok(Self::Frequency),
+            "recent" => Ok(Self::Recent),
+            other => Err(format!("unknown sort order '{}'", other)),
+        }
+    }
+}
+
+// The sort is stable, so bookmarks with equal usage keep their config order.
+fn sort_bookmarks(bookmarks: &mut [Bookmark], sort_by: SortBy, history: &History) {
+    let usage: HashMap<&str, (usize, u64)> = history
+        .entries
+        .iter()
+        .map(|entry| (entry.name.as_str(), (entry.count, entry.last_used)))
+        .collect();
+    let usage_of = |bookmark: &Bookmark| {
+        usage
+            .get(bookmark.name.as_str())
+            .copied()
+            .unwrap_or_default()
+    };
+
+    match sort_by {
+        SortBy::Config => {}
+        SortBy::Name => sort_by_name(bookmarks),
+        SortBy::Frequency => bookmarks.sort_by_key(|b| std::cmp::Reverse(usage_of(b).0)),
+        SortBy::Recent => bookmarks.sort_by_key(|b| std::cmp::Reverse(usage_of(b).1)),
+    }
+}
+
+// Builds the separator from the shell operator, optionally preceded by a
+// line continuation so every command goes on its own line.
+fn join_separator(command_separator: &str, multiline: bool) -> String {
+    if multiline {
+        format!(" \\\n{} ", command_separator)
+    } else {
+        format!(" {} ", command_separator)
+    }
+}
+
+// The 1-based line that sets the name, like `- name: deploy` in YAML,
+// `name = "deploy"` in TOML or `"name": "deploy"` in JSON.
+fn find_name_line(content: &str, name: &str) -> Option<usize> {
+    let unquote = |value: &str| {
+        let value = value.trim().trim_end_matches(',');
+        value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value)
+            .to_string()
+    };
+
+    content
+        .lines()
+        .position(|line| {
+            let line = line.trim_start_matches(|c: char| c.is_whitespace() || c == '-' || c == '{');
+            let Some(rest) = line
+                .strip_prefix("name")
+                .or_else(|| line.strip_prefix("\"name\""))
+            else {
+                return false;
+            };
+            let Some(value) = rest.trim_start().strip_prefix([':', '=']) else {
+                return false;
+            };
+            // Flow mappings continue after the name.
+            let value = value.split([',', '}']).next().unwrap_or(value);
+            unquote(value) == name
+        })
+        .map(|i| i + 1)
+}
+
+// The first free name of "<name> (copy)", "<name> (copy 2)", ...
+fn copy_name(name: &str, names: &HashSet<&str>) -> String {
+    let mut copy = format!("{} (copy)", name);
+    let mut n = 2;
+    while names.contains(copy.as_str()) {
+        copy = format!("{} (copy {})", name, n);
+        n += 1;
+    }
+    copy
+}
+
+// Hash of the file content, or None if it can't be read.
+fn file_snapshot(path: &Path) -> Option<u64> {
+    let content = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+// Accepts a palette index or the name of the matching default theme color.
+fn parse_color(value: &str) -> Option<usize> {
+    match value.trim().to_lowercase().as_str() {
+        "orange" => Some(0),
+        "cyan" => Some(1),
+        "green" => Some(2),
+        "pink" | "magenta" => Some(3),
+        value => value.parse::<usize>().ok(),
     }
 }
 
@@ -41,12 +225,41 @@ fn reindex_labels(labels: &mut [Label]) {
     }
 }
 
+fn reindex_workflows(workflows: &mut [Workflow]) {
+    for (i, workflow) in workflows.iter_mut().enumerate() {
+        workflow.id = i + 1;
+    }
+}
+
 fn reindex_editable_files(files: &mut [EditableFile]) {
     for (i, file) in files.iter_mut().enumerate() {
         file.id = i + 1;
     }
 }
 
+fn not_in_file(bookmark: &Bookmark, path: &Path) -> Box<dyn std::error::Error> {
+    format!(
+        "Bookmark '{}' not found in '{}'",
+        bookmark.name,
+        path.display()
+    )
+    .into()
+}
+
+// Names of bookmarks that are new or differ from the previous snapshot.
+// Nothing is reported on the first load, when there is no snapshot yet.
+fn modified_bookmarks(previous: &HashMap<String, u64>, bookmarks: &[Bookmark]) -> HashSet<String> {
+    if previous.is_empty() {
+        return HashSet::new();
+    }
+
+    bookmarks
+        .iter()
+        .filter(|bookmark| previous.get(&bookmark.name) != Some(&bookmark.fingerprint()))
+        .map(|bookmark| bookmark.name.clone())
+        .collect()
+}
+
 impl State {
     fn editable_files(&self) -> io::Result<Vec<EditableFile>> {
         let mut files = vec![EditableFile {
@@ -54,6 +267,15 @@ impl State {
             path: self.filename.clone(),
         }];
 
+        for filename in &self.override_filenames {
+            if self.get_cwd().join(filename).exists() {
+                files.push(EditableFile {
+                    id: files.len() + 1,
+                    path: filename.clone(),
+                });
+            }
+        }
+
         for path in self.list_extra_config_paths()? {
             if let Ok(relative_path) = path.strip_prefix(self.get_cwd()) {
                 files.push(EditableFile {
@@ -73,7 +295,7 @@ impl State {
 
     pub(crate) fn refresh_editable_files(&mut self) -> io::Result<Vec<EditableFile>> {
         let files = self.editable_files()?;
-        self.editable_files = FilteredList::new(files.clone());
+        self.editable_files = FilteredList::new(files.clone()).with_wrap(self.wrap_navigation);
         Ok(files)
     }
 
@@ -81,7 +303,9 @@ impl State {
         let path = self.get_path();
         if !path.exists() {
             let conf: Config = Config::default();
-            let serialized = serde_yaml::to_string(&conf).expect("Failed to serialize bookmarks.");
+            let serialized = ConfigFormat::from_path(&path)
+                .serialize(&conf)
+                .expect("Failed to serialize bookmarks.");
             let mut file = fs::File::create(&path)?;
             file.write_all(serialized.as_bytes())?;
         }
@@ -100,11 +324,56 @@ impl State {
         let mut content = String::new();
         file.read_to_string(&mut content)?;
 
-        Ok(serde_yaml::from_str(&content)?)
+        ConfigFormat::from_path(path).parse(&content)
+    }
+
+    // Reads the config and merges its includes, resolved relative to the cwd,
+    // depth-first in the listed order. Only a file including itself through
+    // the stack of includes is circular; a file included again through
+    // another path is merged just once.
+    fn read_config_with_includes(
+        &self,
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+        seen: &mut HashSet<PathBuf>,
+    ) -> Result<Config, Box<dyn std::error::Error>> {
+        if stack.iter().any(|p| p == path) {
+            return Err(format!("Circular include detected for '{}'", path.display()).into());
+        }
+        seen.insert(path.to_path_buf());
+        stack.push(path.to_path_buf());
+
+        let mut config = Self::read_config(path)?;
+        let source = path.strip_prefix(self.get_cwd()).unwrap_or(path);
+        for bookmark in &mut config.bookmarks {
+            bookmark.source = Some(source.display().to_string());
+        }
+        for include in std::mem::take(&mut config.includes) {
+            let include_path = self.get_cwd().join(&include);
+            if seen.contains(&include_path) && !stack.contains(&include_path) {
+                continue;
+            }
+            let included = self.read_config_with_includes(&include_path, stack, seen)?;
+            config.merge(included).map_err(|err| {
+                io::Error::other(format!(
+                    "Failed to merge included config '{}' into '{}': {}",
+                    include,
+                    path.display(),
+                    err
+                ))
+            })?;
+        }
+
+        stack.pop();
+        Ok(config)
     }
 
     fn read_file_config(&self, file: &EditableFile) -> Result<Config, Box<dyn std::error::Error>> {
-        let mut config = Self::read_config(&self.editable_file_path(file))?;
+        let mut config = self.read_config_with_includes(
+            &self.editable_file_path(file),
+            &mut Vec::new(),
+            &mut HashSet::new(),
+        )?;
         let managed_label = file.managed_label(&self.filename, &self.dirname);
 
         for bookmark in &mut config.bookmarks {
@@ -127,26 +396,264 @@ impl State {
         let mut extra_files = fs::read_dir(&dir_path)?
             .map(|entry| entry.map(|entry| entry.path()))
             .collect::<Result<Vec<_>, _>>()?;
-        extra_files.retain(|path| path.is_file() && Self::is_yaml_file(path));
+        extra_files.retain(|path| path.is_file() && ConfigFormat::is_config_file(path));
         extra_files.sort();
 
         Ok(extra_files)
     }
 
-    fn is_yaml_file(path: &Path) -> bool {
-        path.extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| matches!(ext, "yaml" | "yml"))
-            .unwrap_or(false)
+    // Rewrites the main config file with the bookmark appended. Comments and
+    // formatting of the file are not preserved.
+    pub(crate) fn append_bookmark(
+        &self,
+        bookmark: Bookmark,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.get_path();
+        let mut config = Self::read_config(&path)?;
+        config.bookmarks.push(bookmark);
+
+        let serialized = ConfigFormat::from_path(&path).serialize(&config)?;
+        fs::write(&path, serialized)?;
+
+        Ok(())
+    }
+
+    // Appends a copy of the bookmark under a free " (copy)" name to the main
+    // config file and returns the copy. The file is rewritten by
+    // `append_bookmark`, dropping its comments and formatting.
+    pub(crate) fn duplicate_bookmark(
+        &self,
+        bookmark: &Bookmark,
+    ) -> Result<Bookmark, Box<dyn std::error::Error>> {
+        let managed_label = self
+            .bookmark_file(bookmark)?
+            .managed_label(&self.filename, &self.dirname);
+        let names: HashSet<&str> = self
+            .config
+            .bookmarks
+            .iter()
+            .map(|b| b.name.as_str())
+            .collect();
+
+        let mut copy = bookmark.clone();
+        copy.name = copy_name(&bookmark.name, &names);
+        copy.labels.retain(|label| label != &managed_label);
+        copy.fixed_id = None;
+        copy.source = None;
+        copy.pinned = false;
+
+        self.append_bookmark(copy.clone())?;
+        Ok(copy)
+    }
+
+    // The file the bookmark was loaded from, found by its managed file label.
+    fn bookmark_file(
+        &self,
+        bookmark: &Bookmark,
+    ) -> Result<EditableFile, Box<dyn std::error::Error>> {
+        self.editable_files()?
+            .into_iter()
+            .find(|file| {
+                bookmark
+                    .labels
+                    .contains(&file.managed_label(&self.filename, &self.dirname))
+            })
+            .ok_or_else(|| format!("Config file of bookmark '{}' not found", bookmark.name).into())
+    }
+
+    // The path of the file the bookmark is defined in. Bookmarks pulled in
+    // through `includes` carry the managed label of the including file, so
+    // their source is used first.
+    fn bookmark_path(&self, bookmark: &Bookmark) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        match bookmark.source.as_ref() {
+            Some(source) => Ok(self.get_cwd().join(source)),
+            None => Ok(self.editable_file_path(&self.bookmark_file(bookmark)?)),
+        }
+    }
+
+    // The file the bookmark was loaded from and the line of its name, if found.
+    pub(crate) fn bookmark_location(
+        &self,
+        bookmark: &Bookmark,
+    ) -> Result<(PathBuf, Option<usize>), Box<dyn std::error::Error>> {
+        let path = self.bookmark_path(bookmark)?;
+        let line = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| find_name_line(&content, &bookmark.name));
+        Ok((path, line))
+    }
+
+    // Rewrites the file the bookmark was loaded from without it.
+    pub(crate) fn remove_bookmark(
+        &self,
+        bookmark: &Bookmark,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.bookmark_path(bookmark)?;
+        let mut config = Self::read_config(&path)?;
+        let count = config.bookmarks.len();
+        config.bookmarks.retain(|b| b.name != bookmark.name);
+        if config.bookmarks.len() == count {
+            return Err(not_in_file(bookmark, &path));
+        }
+
+        let serialized = ConfigFormat::from_path(&path).serialize(&config)?;
+        fs::write(&path, serialized)?;
+
+        Ok(())
+    }
+
+    // Flips the pinned state of the bookmark in the pins file, leaving the
+    // config files as they are.
+    pub(crate) fn toggle_pinned(
+        &mut self,
+        bookmark: &Bookmark,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.bookmark_path(bookmark)?;
+        let configured = Self::read_config(&path)?
+            .bookmarks
+            .iter()
+            .find(|b| b.name == bookmark.name)
+            .map(|b| b.pinned)
+            .ok_or_else(|| not_in_file(bookmark, &path))?;
+
+        self.pins.toggle(&bookmark.name, configured);
+        self.write_pins()
+    }
+
+    // Writes the bookmarks, with the cmds and vars they reference, to the export file.
+    pub(crate) fn export_bookmarks(
+        &self,
+        names: &[String],
+    ) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = self.get_cwd().join(&self.export_filename);
+        let serialized = ConfigFormat::from_path(&path).serialize(&self.config.export(names))?;
+        fs::write(&path, serialized)?;
+
+        Ok(path)
+    }
+
+    // Writes the expanded commands of all bookmarks to the commands file and
+    // returns its path and how many bookmarks failed to expand.
+    pub(crate) fn dump_commands(&self) -> Result<(PathBuf, usize), Box<dyn std::error::Error>> {
+        let path = self.get_cwd().join(&self.commands_filename);
+        let (dump, errors) = self.commands_dump();
+        fs::write(&path, dump)?;
+
+        Ok((path, errors))
+    }
+
+    // Appends the bookmarks of the import file to the main config file and
+    // returns how many were added and the names skipped as duplicates.
+    pub(crate) fn import_bookmarks(
+        &self,
+    ) -> Result<(usize, Vec<String>), Box<dyn std::error::Error>> {
+        let imported = Self::read_config(&self.get_cwd().join(&self.import_filename))?;
+        let count = imported.bookmarks.len();
+        let taken: HashSet<String> = self
+            .config
+            .bookmarks
+            .iter()
+            .map(|bookmark| bookmark.name.clone())
+            .collect();
+
+        let path = self.get_path();
+        let mut config = Self::read_config(&path)?;
+        let skipped = config.import(imported, &taken);
+
+        let serialized = ConfigFormat::from_path(&path).serialize(&config)?;
+        fs::write(&path, serialized)?;
+
+        Ok((count - skipped.len(), skipped))
+    }
+
+    // Files written by the plugin itself are reloaded right away, so their
+    // events find an unchanged snapshot.
+    pub(crate) fn config_changed(&self, path: &Path) -> bool {
+        let path = self.get_cwd().join(path);
+        match self.config_snapshots.get(&path) {
+            Some(snapshot) => file_snapshot(&path) != *snapshot,
+            None => {
+                path.parent() == Some(self.get_dir_path().as_path())
+                    && ConfigFormat::is_config_file(&path)
+                    && path.is_file()
+            }
+        }
+    }
+
+    fn read_history(&self) -> Result<History, Box<dyn std::error::Error>> {
+        let path = self.get_history_path();
+        if !path.exists() {
+            return Ok(History::default());
+        }
+        Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub(crate) fn write_history(&self) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(
+            self.get_history_path(),
+            serde_yaml::to_string(&self.history)?,
+        )?;
+        Ok(())
+    }
+
+    fn read_pins(&self) -> Result<Pins, Box<dyn std::error::Error>> {
+        let path = self.get_pins_path();
+        if !path.exists() {
+            return Ok(Pins::default());
+        }
+        Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn write_pins(&self) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(self.get_pins_path(), serde_yaml::to_string(&self.pins)?)?;
+        Ok(())
+    }
+
+    fn read_session(&self) -> Result<Option<Session>, Box<dyn std::error::Error>> {
+        let path = self.get_session_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_yaml::from_str(&fs::read_to_string(path)?)?))
+    }
+
+    pub(crate) fn write_session(
+        &self,
+        session: &Session,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(self.get_session_path(), serde_yaml::to_string(session)?)?;
+        Ok(())
+    }
+
+    // Lists the used bookmarks that still exist, most recently used first.
+    pub(crate) fn refresh_recent(&mut self) {
+        let recent = self
+            .history
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                self.config
+                    .bookmarks
+                    .iter()
+                    .find(|bookmark| bookmark.name == entry.name)
+                    .cloned()
+            })
+            .collect();
+        self.recent = FilteredList::new(recent).with_wrap(self.wrap_navigation);
     }
 
     pub(crate) fn load_config(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let files = self.refresh_editable_files().map_err(io::Error::other)?;
+        let (override_files, files): (Vec<_>, Vec<_>) = files
+            .into_iter()
+            .partition(|file| self.override_filenames.contains(&file.path));
         let mut files_iter = files.iter();
         let Some(first_file) = files_iter.next() else {
             return Err(io::Error::other("No editable files found").into());
         };
 
+        // Everything is read before any state is replaced, so a failed reload
+        // keeps the last good config.
         let mut config = self.read_file_config(first_file)?;
         let mut merged_file = first_file;
 
@@ -161,33 +668,108 @@ impl State {
             merged_file = file;
         }
 
-        if self.sort {
-            sort_by_name(&mut config.bookmarks);
-            reindex_bookmarks(&mut config.bookmarks);
+        for filename in &self.override_filenames {
+            if let Some(file) = override_files.iter().find(|file| &file.path == filename) {
+                config.override_with(self.read_file_config(file)?);
+            }
+        }
+
+        let sort_by = self.sort_by.unwrap_or(if self.sort {
+            SortBy::Name
+        } else {
+            SortBy::Config
+        });
+        if sort_by != SortBy::Config {
+            sort_bookmarks(&mut config.bookmarks, sort_by, &self.history);
+        }
+        assign_ids(&mut config.bookmarks).map_err(io::Error::other)?;
+        for (index, bookmark) in config.bookmarks.iter_mut().enumerate() {
+            bookmark.index = index;
+            bookmark.pinned = self.pins.is_pinned(&bookmark.name, bookmark.pinned);
         }
 
         let mut set = HashSet::new();
         let mut labels = Vec::new();
+        let mut counts: HashMap<String, usize> = HashMap::new();
         let mut label_id = 1;
+        let separator = self.label_hierarchy_separator();
         for bookmark in config.bookmarks.iter() {
+            // Hierarchical labels also list the labels they are nested under.
+            let mut names = Vec::new();
             for label in bookmark.labels.iter() {
-                if set.insert(label.clone()) {
-                    labels.push(Label::new(label_id, label.clone()));
+                if let Some(separator) = separator.as_deref() {
+                    names.extend(label_ancestors(label, separator));
+                }
+                names.push(label.clone());
+            }
+            for name in names.iter().collect::<HashSet<_>>() {
+                *counts.entry(name.clone()).or_default() += 1;
+            }
+            for name in names {
+                if set.insert(name.clone()) {
+                    labels.push(Label::new(label_id, name));
                     label_id += 1;
                 };
             }
         }
+        for label in labels.iter_mut() {
+            label.count = counts.get(&label.name).copied().unwrap_or_default();
+        }
 
         if self.sort {
-            sort_by_name(&mut labels);
+            match separator.as_deref() {
+                // Nested labels follow the label they are nested under.
+                Some(separator) => {
+                    labels.sort_by(|a, b| a.name.split(separator).cmp(b.name.split(separator)))
+                }
+                None => sort_by_name(&mut labels),
+            }
             reindex_labels(&mut labels);
         }
 
-        self.labels = FilteredList::new(labels);
+        self.labels = FilteredList::new(labels).with_wrap(self.wrap_navigation);
 
-        self.bookmarks = FilteredList::new(config.bookmarks.clone());
+        let mut workflows: Vec<Workflow> = config
+            .workflows
+            .iter()
+            .map(|(name, steps)| Workflow::new(0, name.clone(), steps.clone()))
+            .collect();
+        // Workflows come from a map, so they are always ordered by name.
+        sort_by_name(&mut workflows);
+        reindex_workflows(&mut workflows);
+
+        self.workflows = FilteredList::new(workflows).with_wrap(self.wrap_navigation);
+
+        self.bookmarks = FilteredList::new(config.bookmarks.clone())
+            .with_limit(self.max_results)
+            .with_wrap(self.wrap_navigation);
+        self.apply_list_order();
+        self.bookmarks.reset_selection();
+        self.labels.reset_selection();
+
+        self.modified_bookmarks =
+            modified_bookmarks(&self.bookmark_fingerprints, &config.bookmarks);
+        self.bookmark_fingerprints = config
+            .bookmarks
+            .iter()
+            .map(|bookmark| (bookmark.name.clone(), bookmark.fingerprint()))
+            .collect();
 
         self.config = config;
+        self.template_cache.borrow_mut().clear();
+        self.config_snapshots = override_files
+            .iter()
+            .chain(files.iter())
+            .map(|file| {
+                let path = self.editable_file_path(file);
+                let snapshot = file_snapshot(&path);
+                (path, snapshot)
+            })
+            .collect();
+
+        self.refresh_recent();
+
+        self.diagnostics = self.validate();
 
         Ok(())
     }
@@ -198,6 +780,9 @@ impl State {
             PermissionType::ChangeApplicationState,
             PermissionType::WriteToStdin,
             PermissionType::OpenFiles,
+            PermissionType::OpenTerminalsOrPlugins,
+            PermissionType::RunCommands,
+            PermissionType::ReadCliPipes,
         ]);
 
         if let Some(value) = configuration.get(CONFIGURATION_EXEC) {
@@ -209,6 +794,15 @@ impl State {
             })
         }
 
+        if let Some(value) = configuration.get(CONFIGURATION_DRY_RUN) {
+            self.dry_run = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_DRY_RUN}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            })
+        }
+
         if let Some(value) = configuration.get(CONFIGURATION_SORT) {
             self.sort = value.trim().parse::<bool>().unwrap_or_else(|_| {
                 self.error_mgr.handle_error(
@@ -218,6 +812,65 @@ impl State {
             })
         }
 
+        if let Some(value) = configuration.get(CONFIGURATION_SORT_BY) {
+            self.sort_by = match value.parse::<SortBy>() {
+                Ok(sort_by) => Some(sort_by),
+                Err(_) => {
+                    self.error_mgr.handle_error(
+                        format!("'{CONFIGURATION_SORT_BY}' config value must be 'config', 'name', 'frequency' or 'recent', but it's '{value}'. The '{CONFIGURATION_SORT}' value is used.")
+                    );
+                    None
+                }
+            }
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_MAX_RESULTS) {
+            self.max_results = match value.trim().parse::<usize>() {
+                Ok(0) => None,
+                Ok(max_results) => Some(max_results),
+                Err(_) => {
+                    self.error_mgr.handle_error(
+                        format!("'{CONFIGURATION_MAX_RESULTS}' config value must be a number, but it's '{value}'. No limit is used.")
+                    );
+                    None
+                }
+            }
+        }
+
+        let multiline = match configuration.get(CONFIGURATION_MULTILINE) {
+            Some(value) => value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_MULTILINE}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            }),
+            // Line continuations leave shells waiting at a `cmdand>` prompt
+            // when pasted into some terminals, so one line is the default.
+            None => false,
+        };
+        let command_separator = configuration
+            .get(CONFIGURATION_COMMAND_SEPARATOR)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+            .unwrap_or("&&");
+        self.separator = join_separator(command_separator, multiline);
+
+        if let Some(value) = configuration.get(CONFIGURATION_SHELL) {
+            if !value.trim().is_empty() {
+                self.shell = Some(value.trim().to_string());
+            }
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_QUOTE_VARS) {
+            self.quote_vars = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_QUOTE_VARS}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            })
+        }
+
+        // An explicit separator takes precedence over the parts above.
         if let Some(value) = configuration.get(CONFIGURATION_SEPARATOR) {
             self.separator = value.clone();
         }
@@ -232,31 +885,175 @@ impl State {
         }
 
         if let Some(value) = configuration.get(CONFIGURATION_CHROME_COLOR) {
-            self.ui_style.chrome_color = value.trim().parse::<usize>().unwrap_or_else(|_| {
+            self.ui_style.chrome_color = parse_color(value).unwrap_or_else(|| {
                 self.error_mgr.handle_error(
-                    format!("'{CONFIGURATION_CHROME_COLOR}' config value must be a number, but it's '{value}'. The 2 is used.")
+                    format!("'{CONFIGURATION_CHROME_COLOR}' config value must be a number or a color name, but it's '{value}'. The 2 is used.")
                 );
                 2
             })
         }
 
         if let Some(value) = configuration.get(CONFIGURATION_MATCH_COLOR) {
-            self.ui_style.match_color = value.trim().parse::<usize>().unwrap_or_else(|_| {
+            self.ui_style.match_color = Some(parse_color(value).unwrap_or_else(|| {
                 self.error_mgr.handle_error(
-                    format!("'{CONFIGURATION_MATCH_COLOR}' config value must be a number, but it's '{value}'. The 3 is used.")
+                    format!("'{CONFIGURATION_MATCH_COLOR}' config value must be a number or a color name, but it's '{value}'. The 3 is used.")
                 );
                 3
-            })
+            }));
         }
 
         if let Some(value) = configuration.get(CONFIGURATION_ACTIVE_ITEM_COLOR) {
-            self.ui_style.active_item_color =
-                value.trim().parse::<usize>().unwrap_or_else(|_| {
+            self.ui_style.active_item_color = Some(parse_color(value).unwrap_or_else(|| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_ACTIVE_ITEM_COLOR}' config value must be a number or a color name, but it's '{value}'. The 0 is used.")
+                );
+                0
+            }));
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_SEARCH_DESCRIPTION) {
+            self.search_description = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_SEARCH_DESCRIPTION}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            })
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_GROUP_VIEW) {
+            self.group_view = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_GROUP_VIEW}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            });
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_DESCRIBE_POPUP) {
+            self.describe_popup = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_DESCRIBE_POPUP}' config value must be 'true' or 'false', but it's '{value}'. The true is used.")
+                );
+                true
+            });
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_WRAP_DESCRIPTION) {
+            self.wrap_description = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_WRAP_DESCRIPTION}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            })
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_VIM_KEYS) {
+            self.vim_keys = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_VIM_KEYS}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            })
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_AUTO_RELOAD) {
+            self.auto_reload = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_AUTO_RELOAD}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            })
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_ID_MATCH) {
+            self.id_match = value.parse::<IdMatch>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_ID_MATCH}' config value must be 'prefix', 'exact' or 'contains', but it's '{value}'. The 'prefix' is used.")
+                );
+                IdMatch::Prefix
+            });
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_ACRONYM_SEARCH) {
+            self.acronym_search = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_ACRONYM_SEARCH}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            });
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_WRAP_NAVIGATION) {
+            self.wrap_navigation = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_WRAP_NAVIGATION}' config value must be 'true' or 'false', but it's '{value}'. The true is used.")
+                );
+                true
+            });
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_CLOSE_AFTER_RUN) {
+            self.close_after_run = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_CLOSE_AFTER_RUN}' config value must be 'true' or 'false', but it's '{value}'. The true is used.")
+                );
+                true
+            });
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_EDIT_DUPLICATE) {
+            self.edit_duplicate = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_EDIT_DUPLICATE}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            });
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_SHOW_FOOTER) {
+            self.show_footer = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_SHOW_FOOTER}' config value must be 'true' or 'false', but it's '{value}'. The true is used.")
+                );
+                true
+            });
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_PRESERVE_FILTER_ON_MODE_SWITCH) {
+            self.preserve_filter_on_mode_switch = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_PRESERVE_FILTER_ON_MODE_SWITCH}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            });
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_HIERARCHICAL_LABELS) {
+            self.hierarchical_labels = value.trim().parse::<bool>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_HIERARCHICAL_LABELS}' config value must be 'true' or 'false', but it's '{value}'. The false is used.")
+                );
+                false
+            });
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_LABEL_SEPARATOR) {
+            if !value.is_empty() {
+                self.label_separator = value.clone();
+            }
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_LABEL_MATCH) {
+            self.label_match_all = match value.trim() {
+                "any" => false,
+                "all" => true,
+                _ => {
                     self.error_mgr.handle_error(
-                        format!("'{CONFIGURATION_ACTIVE_ITEM_COLOR}' config value must be a number, but it's '{value}'. The 0 is used.")
+                        format!("'{CONFIGURATION_LABEL_MATCH}' config value must be 'any' or 'all', but it's '{value}'. The any is used.")
                     );
-                    0
-                })
+                    false
+                }
+            }
         }
 
         if let Some(value) = configuration.get(CONFIGURATION_IGNORE_CASE) {
@@ -286,9 +1083,16 @@ impl State {
             })
         }
 
+        // A comma-separated list: the first file is the main one, the rest override it in order.
         if let Some(value) = configuration.get(CONFIGURATION_FILENAME) {
-            if !value.is_empty() {
-                self.filename = value.clone();
+            let mut filenames = value
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(String::from);
+            if let Some(filename) = filenames.next() {
+                self.filename = filename;
+                self.override_filenames = filenames.collect();
             }
         }
 
@@ -298,6 +1102,101 @@ impl State {
             }
         }
 
+        if let Some(value) = configuration.get(CONFIGURATION_HISTORY_FILENAME) {
+            if !value.is_empty() {
+                self.history_filename = value.clone();
+            }
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_EXPORT_FILENAME) {
+            if !value.is_empty() {
+                self.export_filename = value.clone();
+            }
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_IMPORT_FILENAME) {
+            if !value.is_empty() {
+                self.import_filename = value.clone();
+            }
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_COMMANDS_FILENAME) {
+            if !value.is_empty() {
+                self.commands_filename = value.clone();
+            }
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_RECENT_LIMIT) {
+            self.recent_limit = value.trim().parse::<usize>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_RECENT_LIMIT}' config value must be a number, but it's '{value}'. The 20 is used.")
+                );
+                20
+            })
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_MAX_TEMPLATE_DEPTH) {
+            self.max_template_depth = value.trim().parse::<usize>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_MAX_TEMPLATE_DEPTH}' config value must be a number, but it's '{value}'. The 32 is used.")
+                );
+                32
+            })
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_SESSION_FILENAME) {
+            if !value.is_empty() {
+                self.session_filename = value.clone();
+            }
+        }
+
+        if let Some(value) = configuration.get(CONFIGURATION_PINS_FILENAME) {
+            if !value.is_empty() {
+                self.pins_filename = value.clone();
+            }
+        }
+
+        let initial_mode = configuration.get(CONFIGURATION_INITIAL_MODE).map(|value| {
+            value.parse::<Mode>().unwrap_or_else(|_| {
+                self.error_mgr.handle_error(
+                    format!("'{CONFIGURATION_INITIAL_MODE}' config value must be 'bookmarks', 'labels', 'usage', 'workflows' or 'recent', but it's '{value}'. The 'bookmarks' is used.")
+                );
+                Mode::Bookmarks
+            })
+        });
+        let initial_filter_mode = configuration
+            .get(CONFIGURATION_INITIAL_FILTER_MODE)
+            .and_then(|value| match value.parse::<FilterMode>() {
+                Ok(filter_mode) => Some(filter_mode),
+                Err(_) => {
+                    self.error_mgr.handle_error(
+                        format!("'{CONFIGURATION_INITIAL_FILTER_MODE}' config value must be 'name', 'id', 'label', 'regex' or 'all', but it's '{value}'. It is ignored.")
+                    );
+                    None
+                }
+            });
+        let initial_filter = configuration.get(CONFIGURATION_INITIAL_FILTER).cloned();
+
+        match self.read_history() {
+            Ok(history) => self.history = history,
+            Err(e) => {
+                self.error_mgr.handle_error(format!(
+                    "Failed to read history '{}': {}.",
+                    self.history_filename, e
+                ));
+            }
+        }
+
+        match self.read_pins() {
+            Ok(pins) => self.pins = pins,
+            Err(e) => {
+                self.error_mgr.handle_error(format!(
+                    "Failed to read pins '{}': {}.",
+                    self.pins_filename, e
+                ));
+            }
+        }
+
         if let Err(e) = self.create_config_if_not_exists() {
             self.error_mgr.handle_crit_error(format!(
                 "Failed to initialize config storage '{}', '{}': {}.",
@@ -321,16 +1220,165 @@ impl State {
             }
         }
 
-        subscribe(&[EventType::Key]);
+        self.report_diagnostics();
+
+        match self.read_session() {
+            Ok(Some(session)) => self.restore_session(session),
+            Ok(None) => {}
+            Err(e) => {
+                self.error_mgr.handle_error(format!(
+                    "Failed to read session '{}': {}.",
+                    self.session_filename, e
+                ));
+            }
+        }
+
+        // A configured launch view overrides the restored session.
+        if initial_mode.is_some() || initial_filter.is_some() || initial_filter_mode.is_some() {
+            if let Err(e) = self.set_initial_view(
+                initial_mode.unwrap_or_default(),
+                initial_filter.unwrap_or_default(),
+                initial_filter_mode.unwrap_or_default(),
+            ) {
+                self.error_mgr.handle_error(format!(
+                    "'{CONFIGURATION_INITIAL_FILTER_MODE}' config value is ignored: {e}. The 'name' is used."
+                ));
+            }
+        }
+
+        self.plugin_id = Some(get_plugin_ids().plugin_id);
+
+        subscribe(&[
+            EventType::Key,
+            EventType::CommandPaneExited,
+            EventType::Timer,
+            EventType::PermissionRequestResult,
+            EventType::RunCommandResult,
+            EventType::BeforeClose,
+            EventType::FileSystemCreate,
+            EventType::FileSystemUpdate,
+            EventType::FileSystemDelete,
+            EventType::PaneUpdate,
+            EventType::ModeUpdate,
+        ]);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{reindex_bookmarks, reindex_editable_files, reindex_labels, sort_by_name};
+    use super::{
+        copy_name, file_snapshot, find_name_line, join_separator, modified_bookmarks, parse_color,
+        reindex_editable_files, reindex_labels, sort_bookmarks, sort_by_name, SortBy,
+    };
     use crate::bookmark::Bookmark;
+    use crate::config::assign_ids;
     use crate::editable_file::EditableFile;
+    use crate::history::History;
     use crate::label::Label;
+    use crate::State;
+    use std::collections::{HashMap, HashSet};
+    use std::path::PathBuf;
+
+    // A config dir in the temp dir, removed again when dropped.
+    struct ConfigDir(PathBuf);
+
+    impl ConfigDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn path(&self, file: &str) -> PathBuf {
+            self.0.join(file)
+        }
+
+        // Writes the main config file and loads it into the state, which is
+        // pointed at the file and at a config dir that doesn't exist.
+        fn load(&self, config: &str, state: State) -> State {
+            let path = self.path("bookmarks.yaml");
+            std::fs::write(&path, config).unwrap();
+            let mut state = State {
+                filename: path.to_string_lossy().to_string(),
+                dirname: self.path("missing").to_string_lossy().to_string(),
+                pins_filename: self.path("pins.yaml").to_string_lossy().to_string(),
+                ..state
+            };
+            state.load_config().unwrap();
+            state
+        }
+    }
+
+    impl Drop for ConfigDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn unsorted() -> State {
+        State {
+            sort: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn find_name_line_matches_yaml_toml_and_json_entries() {
+        let yaml = "bookmarks:\n  - name: build\n    cmds: [make]\n  - name: \"deploy\"\n";
+        assert_eq!(find_name_line(yaml, "deploy"), Some(4));
+        assert_eq!(find_name_line(yaml, "build"), Some(2));
+
+        let toml = "[[bookmarks]]\nname = \"deploy\"\ncmds = [\"make\"]\n";
+        assert_eq!(find_name_line(toml, "deploy"), Some(2));
+
+        let json = "{\"bookmarks\": [\n  {\"name\": \"deploy\", \"cmds\": []}\n]}";
+        assert_eq!(find_name_line(json, "deploy"), Some(2));
+
+        let flow = "bookmarks:\n  - {name: deploy, cmds: [make]}\n";
+        assert_eq!(find_name_line(flow, "deploy"), Some(2));
+
+        assert_eq!(find_name_line(yaml, "deploy-all"), None);
+    }
+
+    #[test]
+    fn copy_name_skips_taken_names() {
+        let names = HashSet::from(["build", "build (copy)", "build (copy 2)"]);
+        assert_eq!(copy_name("deploy", &names), "deploy (copy)");
+        assert_eq!(copy_name("build", &names), "build (copy 3)");
+    }
+
+    #[test]
+    fn duplicate_bookmark_appends_a_copy_to_the_main_file() {
+        let dir = ConfigDir::new("zellij_bookmarks_duplicate");
+        let mut state = dir.load(
+            "bookmarks: [{name: build, id: 7, pinned: true, cmds: [make], labels: [dev]}]",
+            unsorted(),
+        );
+
+        let build = state.config.bookmarks[0].clone();
+        let copy = state.duplicate_bookmark(&build).unwrap();
+        assert_eq!(copy.name, "build (copy)");
+        state.load_config().unwrap();
+
+        let copy = &state.config.bookmarks[1];
+        assert_eq!(copy.name, "build (copy)");
+        assert_eq!(copy.cmds, vec!["make"]);
+        assert!(copy.labels.contains(&"dev".to_string()));
+        assert_eq!(copy.fixed_id, None);
+        assert!(!copy.pinned);
+        assert_eq!(
+            state.duplicate_bookmark(&build).unwrap().name,
+            "build (copy 2)"
+        );
+    }
+
+    #[test]
+    fn parse_color_accepts_indices_and_names() {
+        assert_eq!(parse_color("1"), Some(1));
+        assert_eq!(parse_color(" Green "), Some(2));
+        assert_eq!(parse_color("magenta"), Some(3));
+        assert_eq!(parse_color("purple"), None);
+    }
 
     #[test]
     fn sort_by_name_orders_bookmarks_and_reindexes_them() {
@@ -350,7 +1398,7 @@ mod tests {
         ];
 
         sort_by_name(&mut bookmarks);
-        reindex_bookmarks(&mut bookmarks);
+        assign_ids(&mut bookmarks).unwrap();
 
         assert_eq!(bookmarks[0].name, "alpha");
         assert_eq!(bookmarks[0].id, 1);
@@ -358,6 +1406,36 @@ mod tests {
         assert_eq!(bookmarks[1].id, 2);
     }
 
+    #[test]
+    fn join_separator_emits_line_continuation_only_when_multiline() {
+        assert_eq!(join_separator("&&", true), " \\\n&& ");
+        assert_eq!(join_separator("&&", false), " && ");
+        assert_eq!(join_separator(";", false), " ; ");
+    }
+
+    #[test]
+    fn sort_bookmarks_by_usage_is_stable() {
+        let bookmark = |name: &str| Bookmark {
+            name: name.to_string(),
+            cmds: vec![format!("echo {}", name)],
+            ..Default::default()
+        };
+        let mut history = History::default();
+        history.record("c", 1, 10);
+        history.record("c", 2, 10);
+        history.record("b", 3, 10);
+
+        let mut bookmarks = vec![bookmark("a"), bookmark("b"), bookmark("c"), bookmark("d")];
+        sort_bookmarks(&mut bookmarks, SortBy::Frequency, &history);
+        let names: Vec<&str> = bookmarks.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["c", "b", "a", "d"]);
+
+        let mut bookmarks = vec![bookmark("a"), bookmark("b"), bookmark("c"), bookmark("d")];
+        sort_bookmarks(&mut bookmarks, SortBy::Recent, &history);
+        let names: Vec<&str> = bookmarks.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "c", "a", "d"]);
+    }
+
     #[test]
     fn sort_by_name_orders_labels_and_editable_files_and_reindexes_them() {
         let mut labels = vec![
@@ -390,4 +1468,300 @@ mod tests {
         assert_eq!(files[1].path, "z.yml");
         assert_eq!(files[1].id, 2);
     }
+
+    #[test]
+    fn modified_bookmarks_reports_new_and_changed_entries() {
+        let unchanged = Bookmark {
+            name: "unchanged".to_string(),
+            cmds: vec!["echo same".to_string()],
+            ..Default::default()
+        };
+        let mut changed = Bookmark {
+            name: "changed".to_string(),
+            cmds: vec!["echo old".to_string()],
+            ..Default::default()
+        };
+        let previous = HashMap::from([
+            (unchanged.name.clone(), unchanged.fingerprint()),
+            (changed.name.clone(), changed.fingerprint()),
+        ]);
+
+        changed.cmds = vec!["echo new".to_string()];
+        let added = Bookmark {
+            name: "added".to_string(),
+            cmds: vec!["echo added".to_string()],
+            ..Default::default()
+        };
+
+        let modified = modified_bookmarks(&previous, &[unchanged, changed, added]);
+
+        assert_eq!(
+            modified,
+            HashSet::from(["changed".to_string(), "added".to_string()])
+        );
+    }
+
+    #[test]
+    fn modified_bookmarks_is_empty_on_first_load() {
+        let bookmark = Bookmark {
+            name: "first".to_string(),
+            ..Default::default()
+        };
+
+        assert!(modified_bookmarks(&HashMap::new(), &[bookmark]).is_empty());
+    }
+
+    #[test]
+    fn included_bookmarks_are_changed_in_the_file_they_come_from() {
+        let dir = ConfigDir::new("zellij_bookmarks_included_changes");
+        let included = dir.path("team.yaml");
+        std::fs::write(
+            &included,
+            "bookmarks:\n  - name: deploy\n    cmds: [make deploy]\n  - name: lint\n    cmds: [make lint]\n",
+        )
+        .unwrap();
+        let mut state = dir.load(
+            &format!(
+                "includes: ['{}']\nbookmarks: [{{name: build, cmds: [make]}}]",
+                included.display()
+            ),
+            unsorted(),
+        );
+
+        let deploy = state.config.bookmarks[1].clone();
+        assert_eq!(
+            state.bookmark_location(&deploy).unwrap(),
+            (included.clone(), Some(2))
+        );
+        state.toggle_pinned(&deploy).unwrap();
+        state.load_config().unwrap();
+        assert!(state
+            .config
+            .bookmarks
+            .iter()
+            .any(|b| b.name == "deploy" && b.pinned));
+
+        let lint = state
+            .config
+            .bookmarks
+            .iter()
+            .find(|b| b.name == "lint")
+            .cloned()
+            .unwrap();
+        state.remove_bookmark(&lint).unwrap();
+        state.load_config().unwrap();
+        let names: Vec<&str> = state
+            .config
+            .bookmarks
+            .iter()
+            .map(|b| b.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["build", "deploy"]);
+
+        // Removed meanwhile, so there is nothing left to change.
+        assert!(state.remove_bookmark(&lint).is_err());
+        assert!(state.toggle_pinned(&lint).is_err());
+    }
+
+    #[test]
+    fn config_changed_compares_the_file_with_its_snapshot() {
+        let path = std::env::temp_dir().join("zellij_bookmarks_config_changed.yaml");
+        std::fs::write(&path, "bookmarks: []").unwrap();
+        let state = State {
+            config_snapshots: HashMap::from([(path.clone(), file_snapshot(&path))]),
+            ..Default::default()
+        };
+
+        assert!(!state.config_changed(&path));
+        std::fs::write(&path, "bookmarks: [{name: a, cmds: [ls]}]").unwrap();
+        assert!(state.config_changed(&path));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(state.config_changed(&path));
+    }
+
+    #[test]
+    fn failed_reload_keeps_the_last_good_config() {
+        let dir = ConfigDir::new("zellij_bookmarks_failed_reload");
+        let mut state = dir.load("bookmarks: [{name: build, cmds: [make]}]", State::default());
+
+        std::fs::write(dir.path("bookmarks.yaml"), "bookmarks: [{name: build").unwrap();
+        assert!(state.load_config().is_err());
+
+        assert_eq!(state.config.bookmarks.len(), 1);
+        assert_eq!(state.bookmarks.len(), 1);
+        assert_eq!(state.labels.len(), 1);
+    }
+
+    #[test]
+    fn labels_count_the_bookmarks_carrying_them() {
+        let dir = ConfigDir::new("zellij_bookmarks_label_counts");
+        let state = dir.load(
+            "bookmarks:\n  - {name: up, cmds: [up], labels: [docker, dev, docker]}\n  - {name: down, cmds: [down], labels: [docker]}\n",
+            State::default(),
+        );
+
+        let counts: HashMap<String, usize> = state
+            .labels
+            .iter()
+            .map(|(_, item)| (item.value.name.clone(), item.value.count))
+            .collect();
+        assert_eq!(counts["docker"], 2);
+        assert_eq!(counts["dev"], 1);
+    }
+
+    #[test]
+    fn hierarchical_labels_list_and_count_their_parents() {
+        let dir = ConfigDir::new("zellij_bookmarks_hierarchical_labels");
+        let state = dir.load(
+            "bookmarks:\n  - {name: a, cmds: [a], labels: [infra/aws, infra-old]}\n  - {name: g, cmds: [g], labels: [infra/gcp]}\n",
+            State {
+                hierarchical_labels: true,
+                label_separator: "/".to_string(),
+                sort: true,
+                ..Default::default()
+            },
+        );
+
+        let labels: Vec<(String, usize)> = state
+            .labels
+            .iter()
+            .map(|(_, item)| (item.value.name.clone(), item.value.count))
+            .filter(|(name, _)| !name.starts_with("file::"))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("infra".to_string(), 2),
+                ("infra/aws".to_string(), 1),
+                ("infra/gcp".to_string(), 1),
+                ("infra-old".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn bookmarks_record_the_file_they_were_read_from() {
+        let dir = ConfigDir::new("zellij_bookmarks_source");
+        let included = dir.path("team.yaml");
+        std::fs::write(
+            &included,
+            "bookmarks: [{name: deploy, cmds: [make deploy]}]",
+        )
+        .unwrap();
+        let state = dir.load(
+            &format!(
+                "includes: ['{}']\nbookmarks: [{{name: build, cmds: [make]}}]",
+                included.display()
+            ),
+            unsorted(),
+        );
+
+        let sources: Vec<Option<String>> = state
+            .config
+            .bookmarks
+            .iter()
+            .map(|bookmark| bookmark.source.clone())
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                Some(dir.path("bookmarks.yaml").display().to_string()),
+                Some(included.display().to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn files_included_through_two_paths_are_merged_once() {
+        let dir = ConfigDir::new("zellij_bookmarks_diamond_include");
+        let shared = dir.path("shared.yaml");
+        std::fs::write(&shared, "bookmarks: [{name: lint, cmds: [make lint]}]").unwrap();
+        for name in ["team.yaml", "ops.yaml"] {
+            std::fs::write(
+                dir.path(name),
+                format!(
+                    "includes: ['{}']\nbookmarks: [{{name: {}, cmds: [make]}}]",
+                    shared.display(),
+                    name.trim_end_matches(".yaml")
+                ),
+            )
+            .unwrap();
+        }
+        let mut state = dir.load(
+            &format!(
+                "includes: ['{}', '{}']\nbookmarks: []",
+                dir.path("team.yaml").display(),
+                dir.path("ops.yaml").display()
+            ),
+            unsorted(),
+        );
+
+        let names: Vec<&str> = state
+            .config
+            .bookmarks
+            .iter()
+            .map(|b| b.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["team", "lint", "ops"]);
+
+        // Including a file from itself is still circular.
+        std::fs::write(
+            &shared,
+            format!(
+                "includes: ['{}']\nbookmarks: []",
+                dir.path("team.yaml").display()
+            ),
+        )
+        .unwrap();
+        let err = state.load_config().unwrap_err();
+        assert!(err.to_string().contains("Circular include detected"));
+    }
+
+    #[test]
+    fn fixed_ids_keep_the_loaded_order() {
+        let dir = ConfigDir::new("zellij_bookmarks_fixed_id_order");
+        let mut state = dir.load(
+            "bookmarks: [{name: zeta, cmds: [a]}, {name: mid, id: 1, cmds: [b]}, {name: alpha, cmds: [c]}]",
+            unsorted(),
+        );
+        let names = |state: &State| -> Vec<String> {
+            state
+                .bookmarks
+                .iter()
+                .map(|(_, item)| item.value.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names(&state), vec!["zeta", "mid", "alpha"]);
+
+        state.sort = true;
+        state.load_config().unwrap();
+        assert_eq!(names(&state), vec!["alpha", "mid", "zeta"]);
+    }
+
+    #[test]
+    fn toggle_pinned_persists_and_lists_pinned_bookmarks_first() {
+        let dir = ConfigDir::new("zellij_bookmarks_toggle_pinned");
+        let mut state = dir.load(
+            "bookmarks: [{name: build, cmds: [make]}, {name: deploy, cmds: [make deploy]}]",
+            unsorted(),
+        );
+
+        let config = std::fs::read_to_string(dir.path("bookmarks.yaml")).unwrap();
+
+        let deploy = state.config.bookmarks[1].clone();
+        state.toggle_pinned(&deploy).unwrap();
+        state.load_config().unwrap();
+        assert_eq!(state.bookmarks.get_selected().unwrap().name, "deploy");
+        assert!(state.bookmarks.get_selected().unwrap().pinned);
+        assert_eq!(
+            std::fs::read_to_string(dir.path("bookmarks.yaml")).unwrap(),
+            config
+        );
+
+        state.toggle_pinned(&deploy).unwrap();
+        state.load_config().unwrap();
+        assert_eq!(state.bookmarks.get_selected().unwrap().name, "build");
+    }
 }
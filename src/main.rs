@@ -2,23 +2,42 @@ mod bookmark;
 mod config;
 mod core;
 mod editable_file;
+mod env;
+mod form;
+mod history;
 mod keybindings;
 mod label;
 mod load;
+mod pins;
+mod pipe;
+mod prompt;
 mod render;
+mod session;
+mod shell;
 mod update;
+mod workflow;
 
 use crate::bookmark::Bookmark;
 use crate::config::Config;
-use crate::core::{ErrorManager, FilterMode, FilteredList, UiStyle};
+use crate::core::{ErrorManager, FilterMode, FilteredList, IdMatch, UiStyle};
 use crate::editable_file::EditableFile;
+use crate::form::BookmarkForm;
+use crate::history::History;
 use crate::keybindings::Keybindings;
 use crate::label::Label;
+use crate::load::{ListOrder, SortBy};
+use crate::pins::Pins;
+use crate::prompt::{PendingRun, VarPrompt};
+use crate::session::Session;
+use crate::update::Renderers;
+use crate::workflow::{Workflow, WorkflowRun};
 
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use std::cell::RefCell;
 use std::cmp::PartialEq;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
 use std::{fmt, path};
 use zellij_tile::prelude::*;
 
@@ -27,22 +46,88 @@ pub const CWD: &str = "/host";
 struct State {
     mode: Mode,
     exec: bool,
+    dry_run: bool,
     sort: bool,
+    sort_by: Option<SortBy>,
+    list_order: ListOrder,
     separator: String,
+    shell: Option<String>,
+    quote_vars: bool,
     ui_style: UiStyle,
     ignore_case: bool,
+    label_match_all: bool,
+    hierarchical_labels: bool,
+    label_separator: String,
+    search_description: bool,
+    wrap_description: bool,
+    vim_keys: bool,
+    acronym_search: bool,
+    id_match: IdMatch,
+    wrap_navigation: bool,
+    show_footer: bool,
+    close_after_run: bool,
+    edit_duplicate: bool,
+    // Content size of the plugin pane at the last render.
+    pane_size: (usize, usize),
+    plugin_id: Option<u32>,
+    preserve_filter_on_mode_switch: bool,
+    vim_search: bool,
+    // Set by the shortcut key, the next key picks the bookmark to run.
+    shortcut_pending: bool,
+    auto_reload: bool,
+    notice: Option<String>,
+    append_args: Option<String>,
     detect_filter_mode: bool,
     fuzzy_search: bool,
     view_desc: bool,
+    // Rows show the commands of bookmarks instead of their names.
+    view_cmds: bool,
+    describe_popup: bool,
+    group_view: bool,
+    show_details: bool,
+    preview: bool,
+    max_results: Option<usize>,
+    page_size: usize,
     filter_mode: FilterMode,
     filter: String,
     filename: String,
+    override_filenames: Vec<String>,
     dirname: String,
+    history_filename: String,
+    export_filename: String,
+    import_filename: String,
+    commands_filename: String,
+    session_filename: String,
+    pins_filename: String,
+    recent_limit: usize,
+    max_template_depth: usize,
     config: Config,
     keybindings: Keybindings,
     bookmarks: FilteredList<Bookmark>,
     labels: FilteredList<Label>,
     editable_files: FilteredList<EditableFile>,
+    workflows: FilteredList<Workflow>,
+    workflow_run: Option<WorkflowRun>,
+    bookmark_fingerprints: HashMap<String, u64>,
+    config_snapshots: HashMap<path::PathBuf, Option<u64>>,
+    modified_bookmarks: HashSet<String>,
+    // Names of the bookmarks marked to run together, in marking order.
+    marked: Vec<String>,
+    // Highlighted entry of the label suggestions.
+    suggestion: Option<usize>,
+    prompt: Option<VarPrompt>,
+    env: HashMap<String, String>,
+    form: Option<BookmarkForm>,
+    confirm_delete: Option<Bookmark>,
+    confirm_run: Option<PendingRun>,
+    back_stack: Vec<Session>,
+    // Rendered commands by bookmark name and fingerprint, see gen_template_command.
+    template_cache: RefCell<HashMap<(String, u64), String>>,
+    renderers: RefCell<Option<Renderers>>,
+    diagnostics: Vec<String>,
+    history: History,
+    pins: Pins,
+    recent: FilteredList<Bookmark>,
     error_mgr: ErrorManager,
 }
 
@@ -51,22 +136,82 @@ impl Default for State {
         Self {
             mode: Default::default(),
             exec: false,
+            dry_run: false,
             sort: true,
-            separator: " \\\n&& ".to_string(),
+            sort_by: None,
+            list_order: ListOrder::default(),
+            separator: " && ".to_string(),
+            shell: None,
+            quote_vars: false,
             ui_style: UiStyle::default(),
             ignore_case: true,
+            label_match_all: false,
+            hierarchical_labels: false,
+            label_separator: "/".to_string(),
+            search_description: false,
+            wrap_description: false,
+            vim_keys: false,
+            acronym_search: false,
+            id_match: IdMatch::Prefix,
+            wrap_navigation: true,
+            show_footer: true,
+            close_after_run: true,
+            edit_duplicate: false,
+            pane_size: (0, 0),
+            plugin_id: None,
+            preserve_filter_on_mode_switch: false,
+            vim_search: false,
+            shortcut_pending: false,
+            auto_reload: false,
+            notice: None,
+            append_args: None,
             detect_filter_mode: true,
             fuzzy_search: true,
             view_desc: false,
+            view_cmds: false,
+            describe_popup: true,
+            group_view: false,
+            show_details: false,
+            preview: false,
+            max_results: None,
+            page_size: 1,
             filter_mode: Default::default(),
             filter: "".to_string(),
             filename: ".zellij_bookmarks.yaml".to_string(),
+            override_filenames: Vec::new(),
             dirname: ".zellij-bookmarks.d".to_string(),
+            history_filename: ".zellij_bookmarks_history.yaml".to_string(),
+            export_filename: ".zellij_bookmarks_export.yaml".to_string(),
+            import_filename: ".zellij_bookmarks_import.yaml".to_string(),
+            commands_filename: ".zellij_bookmarks_commands.jsonl".to_string(),
+            session_filename: ".zellij_bookmarks_session.yaml".to_string(),
+            pins_filename: ".zellij_bookmarks_pins.yaml".to_string(),
+            recent_limit: 20,
+            max_template_depth: 32,
             config: Default::default(),
             keybindings: Default::default(),
             bookmarks: Default::default(),
             labels: Default::default(),
             editable_files: Default::default(),
+            workflows: Default::default(),
+            workflow_run: None,
+            bookmark_fingerprints: HashMap::new(),
+            config_snapshots: HashMap::new(),
+            modified_bookmarks: HashSet::new(),
+            marked: Vec::new(),
+            suggestion: None,
+            prompt: None,
+            env: HashMap::new(),
+            form: None,
+            confirm_delete: None,
+            confirm_run: None,
+            back_stack: Vec::new(),
+            template_cache: RefCell::new(HashMap::new()),
+            renderers: RefCell::new(None),
+            diagnostics: Vec::new(),
+            history: Default::default(),
+            pins: Default::default(),
+            recent: Default::default(),
             error_mgr: ErrorManager::new(),
         }
     }
@@ -80,6 +225,10 @@ enum Mode {
     Labels = 2,
     Usage = 3,
     Edit = 4,
+    Describe = 5,
+    Workflows = 6,
+    Recent = 7,
+    Diagnostics = 8,
 }
 
 trait Navigation {
@@ -96,11 +245,40 @@ impl Navigation for Mode {
 
     fn prev(&self) -> Mode {
         let prev = (*self as u32).saturating_sub(1);
-        Mode::try_from(prev).unwrap_or(Mode::Edit)
+        Mode::try_from(prev).unwrap_or(Mode::Diagnostics)
     }
 
     fn iter() -> impl Iterator<Item = Self> {
-        (1..=4).filter_map(|v| Mode::try_from(v).ok())
+        (1..=8).filter_map(|v| Mode::try_from(v).ok())
+    }
+}
+
+impl Mode {
+    // Filter modes that can be switched to in this mode.
+    fn supports(&self, filter_mode: FilterMode) -> bool {
+        match filter_mode {
+            FilterMode::Name => true,
+            FilterMode::ID => !matches!(self, Self::Usage | Self::Diagnostics),
+            FilterMode::Label | FilterMode::All => {
+                matches!(self, Self::Bookmarks | Self::Describe | Self::Recent)
+            }
+            FilterMode::Regex => matches!(self, Self::Bookmarks | Self::Describe),
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "bookmarks" => Ok(Self::Bookmarks),
+            "labels" => Ok(Self::Labels),
+            "usage" => Ok(Self::Usage),
+            "workflows" => Ok(Self::Workflows),
+            "recent" => Ok(Self::Recent),
+            other => Err(format!("unknown mode '{}'", other)),
+        }
     }
 }
 
@@ -111,6 +289,10 @@ impl fmt::Display for Mode {
             Self::Labels => "Labels",
             Self::Usage => "Usage",
             Self::Edit => "Edit",
+            Self::Describe => "Describe",
+            Self::Workflows => "Workflows",
+            Self::Recent => "Recent",
+            Self::Diagnostics => "Diagnostics",
         };
         write!(f, "{}", name)
     }
@@ -128,6 +310,18 @@ impl State {
     fn get_dir_path(&self) -> path::PathBuf {
         self.get_cwd().join(self.dirname.as_str())
     }
+
+    fn get_history_path(&self) -> path::PathBuf {
+        self.get_cwd().join(self.history_filename.as_str())
+    }
+
+    fn get_session_path(&self) -> path::PathBuf {
+        self.get_cwd().join(self.session_filename.as_str())
+    }
+
+    fn get_pins_path(&self) -> path::PathBuf {
+        self.get_cwd().join(self.pins_filename.as_str())
+    }
 }
 
 impl ZellijPlugin for State {
@@ -139,9 +333,15 @@ impl ZellijPlugin for State {
         self.update(event)
     }
 
+    fn pipe(&mut self, pipe_message: PipeMessage) -> bool {
+        self.pipe(pipe_message)
+    }
+
     fn render(&mut self, rows: usize, cols: usize) {
         self.render(rows, cols);
     }
 }
 
+// The exported `pipe` would take the place of libc's pipe in host test builds.
+#[cfg(not(test))]
 register_plugin!(State);
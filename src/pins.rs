@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Persisted pinned states toggled in the plugin, by bookmark name. They take
+// precedence over the `pinned` field of the config, which is never rewritten.
+#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct Pins {
+    #[serde(default)]
+    pub bookmarks: BTreeMap<String, bool>,
+}
+
+impl Pins {
+    pub(crate) fn is_pinned(&self, name: &str, configured: bool) -> bool {
+        self.bookmarks.get(name).copied().unwrap_or(configured)
+    }
+
+    // A state equal to the configured one is dropped, so the config decides again.
+    pub(crate) fn toggle(&mut self, name: &str, configured: bool) {
+        let pinned = !self.is_pinned(name, configured);
+        if pinned == configured {
+            self.bookmarks.remove(name);
+        } else {
+            self.bookmarks.insert(name.to_string(), pinned);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pins;
+
+    #[test]
+    fn toggle_overrides_the_configured_state_until_toggled_back() {
+        let mut pins = Pins::default();
+        assert!(!pins.is_pinned("build", false));
+        assert!(pins.is_pinned("deploy", true));
+
+        pins.toggle("build", false);
+        pins.toggle("deploy", true);
+        assert!(pins.is_pinned("build", false));
+        assert!(!pins.is_pinned("deploy", true));
+
+        pins.toggle("build", false);
+        pins.toggle("deploy", true);
+        assert!(pins.bookmarks.is_empty());
+    }
+}
@@ -0,0 +1,51 @@
+use std::str::FromStr;
+
+const PIPE_RUN: &str = "run:";
+const PIPE_FILTER: &str = "filter:";
+
+// A message piped to the plugin, e.g. with `zellij pipe -- run:deploy`.
+#[derive(Debug, PartialEq)]
+pub(crate) enum PipeCommand {
+    Run(String),
+    Filter(String),
+}
+
+impl FromStr for PipeCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(name) = s.strip_prefix(PIPE_RUN) {
+            Ok(Self::Run(name.trim().to_string()))
+        } else if let Some(filter) = s.strip_prefix(PIPE_FILTER) {
+            Ok(Self::Filter(filter.to_string()))
+        } else {
+            Err(format!(
+                "unknown pipe message '{}', expected '{}<bookmark>' or '{}<text>'",
+                s, PIPE_RUN, PIPE_FILTER
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PipeCommand;
+
+    #[test]
+    fn pipe_command_parses_run_and_filter() {
+        assert_eq!(
+            " run: deploy \n".parse::<PipeCommand>(),
+            Ok(PipeCommand::Run("deploy".to_string()))
+        );
+        assert_eq!(
+            "filter:git ".parse::<PipeCommand>(),
+            Ok(PipeCommand::Filter("git".to_string()))
+        );
+        assert_eq!(
+            "filter:".parse::<PipeCommand>(),
+            Ok(PipeCommand::Filter(String::new()))
+        );
+        assert!("deploy".parse::<PipeCommand>().is_err());
+    }
+}
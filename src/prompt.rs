@@ -0,0 +1,78 @@
+use crate::bookmark::{Bookmark, RunTarget};
+use std::collections::VecDeque;
+
+// Collects values for the bookmark's `prompt_vars` one at a time before it runs.
+#[derive(Debug, Clone)]
+pub(crate) struct VarPrompt {
+    pub bookmark: Bookmark,
+    pub target: RunTarget,
+    pub input: String,
+    // Appended to the generated command once the vars are collected.
+    pub args: String,
+    pending: VecDeque<String>,
+}
+
+impl VarPrompt {
+    pub(crate) fn new(bookmark: Bookmark, target: RunTarget) -> Self {
+        let pending = VecDeque::from(bookmark.prompt_vars.clone());
+        Self {
+            bookmark,
+            target,
+            input: String::new(),
+            args: String::new(),
+            pending,
+        }
+    }
+
+    pub(crate) fn current(&self) -> Option<&String> {
+        self.pending.front()
+    }
+
+    // Stores the input as the value of the current variable, overriding
+    // config defaults. Returns true once every variable has a value.
+    pub(crate) fn submit(&mut self) -> bool {
+        if let Some(name) = self.pending.pop_front() {
+            let value = std::mem::take(&mut self.input);
+            self.bookmark.vars.insert(name, value);
+        }
+        self.pending.is_empty()
+    }
+}
+
+// A run of a bookmark marked with `confirm`, waiting for y/n.
+#[derive(Debug, Clone)]
+pub(crate) struct PendingRun {
+    pub bookmark: Bookmark,
+    pub target: RunTarget,
+    pub args: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VarPrompt;
+    use crate::bookmark::{Bookmark, RunTarget};
+    use std::collections::HashMap;
+
+    #[test]
+    fn submit_collects_values_in_order_and_overrides_bookmark_vars() {
+        let bookmark = Bookmark {
+            name: "checkout".to_string(),
+            prompt_vars: vec!["remote".to_string(), "branch".to_string()],
+            vars: HashMap::from([("branch".to_string(), "main".to_string())]),
+            ..Default::default()
+        };
+        let mut prompt = VarPrompt::new(bookmark, RunTarget::Current);
+
+        assert_eq!(prompt.current().unwrap(), "remote");
+        prompt.input = "origin".to_string();
+        assert!(!prompt.submit());
+
+        assert_eq!(prompt.current().unwrap(), "branch");
+        prompt.input = "feature".to_string();
+        assert!(prompt.submit());
+
+        assert!(prompt.current().is_none());
+        assert_eq!(prompt.bookmark.vars.get("remote").unwrap(), "origin");
+        assert_eq!(prompt.bookmark.vars.get("branch").unwrap(), "feature");
+    }
+}
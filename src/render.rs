@@ -1,10 +1,113 @@
 use crate::core::keybinding_parser::Keybinding;
-use crate::core::{render_main_menu, render_mode, RESERVE_COLUMN_COUNT, RESERVE_ROW_COUNT};
+use crate::core::{
+    fit_column, menu_height, render_describe_menu, render_footer, render_main_menu, render_mode,
+    render_popup, render_suggestions, take_width, MenuHeader, RESERVE_COLUMN_COUNT,
+};
+use crate::core::{label_completions, FilterMode, GenericFilter, IdMatch, RegexFilter};
+use std::collections::HashMap;
 use zellij_tile::prelude::*;
 
 use super::{Mode, Navigation, State};
+use crate::bookmark::Bookmark;
+use crate::form::{BookmarkForm, FormField};
+use crate::label::{label_ancestors, label_color};
+use crate::load::ListOrder;
+use crate::prompt::VarPrompt;
+
+const MODIFIED_MARKER: &str = " [modified]";
+const PINNED_MARKER: &str = " [pinned]";
+const MARKED_MARKER: &str = "✓";
+const EXIT_SUCCESS_MARKER: &str = " ✔";
+const EXIT_FAILURE_MARKER: &str = " ✘";
+// Whether Enter runs the bookmark or only pastes it for review.
+const EXEC_MARKER: &str = "▶ ";
+const PASTE_MARKER: &str = "✎ ";
+// Header of the bookmarks without a group.
+const UNGROUPED_HEADER: &str = "Other";
+const DESCRIPTION_MATCH_MARKER: &str = " (in description)";
+const DRY_RUN_MARKER: &str = "[dry run]";
+// The preview title and at least one line of the command.
+const PREVIEW_MIN_ROWS: usize = 2;
+const LABEL_COMPLETIONS_SHOWN: usize = 5;
+const LABEL_SUGGESTIONS_SHOWN: usize = 5;
+// Panes at least this wide show names and descriptions side by side.
+const TWO_COLUMN_MIN_COLS: usize = 80;
 
 impl State {
+    fn filter_by(&self) -> String {
+        let mut filter_by = if self.filter_mode == FilterMode::Name && self.fuzzy_search {
+            "Fuzzy name".to_string()
+        } else {
+            self.filter_mode.to_string()
+        };
+        if self.filter_mode == FilterMode::Regex
+            && !RegexFilter::new(self.filter.clone(), self.ignore_case).is_valid()
+        {
+            return "Regex, invalid".to_string();
+        }
+
+        let exclusions = GenericFilter::new(
+            self.filter_mode,
+            self.filter.clone(),
+            self.ignore_case,
+            self.fuzzy_search,
+        )
+        .exclusions();
+        if !exclusions.is_empty() {
+            filter_by.push_str(&format!(", excluding {}", exclusions.join(", ")));
+        }
+        if !self.ignore_case {
+            filter_by.push_str(", case-sensitive");
+        }
+        // Bookmarks mode lists the matching labels below the search line instead.
+        if self.filter_mode == FilterMode::Label && self.mode != Mode::Bookmarks {
+            let completions = label_completions(&self.filter, &self.all_labels(), self.ignore_case);
+            if completions.len() > 1 {
+                let shown = completions.iter().take(LABEL_COMPLETIONS_SHOWN);
+                let mut list = shown.cloned().collect::<Vec<_>>().join(" | ");
+                if completions.len() > LABEL_COMPLETIONS_SHOWN {
+                    list.push_str(" | ...");
+                }
+                filter_by.push_str(&format!(", Tab: {}", list));
+            }
+        }
+        if self.mode == Mode::Bookmarks && !self.marked.is_empty() {
+            filter_by.push_str(&format!(", {} marked", self.marked.len()));
+        }
+        if matches!(self.mode, Mode::Bookmarks | Mode::Labels) && self.list_order != ListOrder::Id {
+            filter_by.push_str(&format!(", sorted by {}", self.list_order));
+        }
+        if self.vim_keys && !self.vim_search && self.filter.is_empty() {
+            filter_by.push_str(", / to type");
+        }
+        if self.shortcut_pending {
+            filter_by.push_str(", press a shortcut key");
+        }
+        filter_by
+    }
+
+    // Name matches always highlight something, so a kept row without indices
+    // in name mode was matched by its description.
+    fn matched_description_only(&self, indices: &[usize]) -> bool {
+        self.search_description
+            && self.filter_mode == FilterMode::Name
+            && !self.filter.is_empty()
+            && !self.filter.starts_with('!')
+            && indices.is_empty()
+    }
+
+    // Matching ids are prefixed by the filter, so it is highlighted as is.
+    // Ids containing it elsewhere or within a range aren't highlighted.
+    fn id_match_len(&self) -> usize {
+        if self.filter_mode == FilterMode::ID
+            && self.id_match != IdMatch::Contains
+            && !self.filter.contains('-')
+        {
+            return self.filter.chars().count();
+        }
+        0
+    }
+
     fn render_usage(&self) {
         let all_modes: Vec<Mode> = Mode::iter().collect();
         render_mode(0, 0, Mode::Usage, &all_modes, &self.ui_style);
@@ -16,49 +119,85 @@ impl State {
         table = table.add_row(vec![
             format!(
                 "{}|{}",
-                BareKey::Esc,
+                self.keybindings.exit,
                 Keybinding::new(KeyModifier::Ctrl, 'c')
             )
             .as_str(),
             "Exit the zellij-bookmarks.",
             "*",
-            "False",
+            "True",
         ]);
         table = table.add_row(vec![
-            format!("{}|{} {}", BareKey::Tab, BareKey::Down, BareKey::Up).as_str(),
+            format!(
+                "{}|{} {}",
+                BareKey::Tab,
+                self.keybindings.down,
+                self.keybindings.up
+            )
+            .as_str(),
             "Navigate through the list of bookmarks or labels.",
             format!("{}|{}", Mode::Bookmarks, Mode::Labels).as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            format!("{} {}", BareKey::PageUp, BareKey::PageDown).as_str(),
+            "Jump through the list one page at a time.",
+            "*",
             "False",
         ]);
         table = table.add_row(vec![
-            format!("{} {}", BareKey::Left, BareKey::Right).as_str(),
+            format!(
+                "{} {}",
+                self.keybindings.prev_mode, self.keybindings.next_mode
+            )
+            .as_str(),
             "Switch between modes.",
             "*",
-            "False",
+            "True",
         ]);
+        if self.vim_keys {
+            table = table.add_row(vec![
+                "j k | h l | /",
+                "Navigate, switch modes or start a search while the filter is empty.",
+                "*",
+                "False",
+            ]);
+        }
         table = table.add_row(vec![
             BareKey::Backspace.to_string().as_str(),
-            "Remove the last character from the filter.",
+            "Remove the last character from the filter, or go back to the previous view when it is empty.",
             format!("{}|{}", Mode::Bookmarks, Mode::Labels).as_str(),
             "False",
         ]);
         table = table.add_row(vec![
-            BareKey::Enter.to_string().as_str(),
+            self.keybindings.run.to_string().as_str(),
             "Paste the selected bookmark into the terminal.",
             Mode::Bookmarks.to_string().as_str(),
-            "False",
+            "True",
         ]);
         table = table.add_row(vec![
-            BareKey::Enter.to_string().as_str(),
+            self.keybindings.run.to_string().as_str(),
             "Find all bookmarks associated with the selected label.",
             Mode::Labels.to_string().as_str(),
-            "False",
+            "True",
         ]);
         table = table.add_row(vec![
-            BareKey::Enter.to_string().as_str(),
+            self.keybindings.run.to_string().as_str(),
             "Open the selected config file in an editor.",
             Mode::Edit.to_string().as_str(),
-            "False",
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.run.to_string().as_str(),
+            "Run the selected workflow step by step in command panes.",
+            Mode::Workflows.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.run.to_string().as_str(),
+            "Run the selected recently used bookmark again.",
+            Mode::Recent.to_string().as_str(),
+            "True",
         ]);
         table = table.add_row(vec![
             format!("{:?} {}", KeyModifier::Ctrl, Mode::Bookmarks as u32).as_str(),
@@ -84,6 +223,30 @@ impl State {
             "*",
             "False",
         ]);
+        table = table.add_row(vec![
+            format!("{:?} {}", KeyModifier::Ctrl, Mode::Describe as u32).as_str(),
+            "Switch to Describe mode to review all bookmarks with descriptions.",
+            "*",
+            "False",
+        ]);
+        table = table.add_row(vec![
+            format!("{:?} {}", KeyModifier::Ctrl, Mode::Workflows as u32).as_str(),
+            "Switch to Workflows mode to browse and launch workflows.",
+            "*",
+            "False",
+        ]);
+        table = table.add_row(vec![
+            format!("{:?} {}", KeyModifier::Ctrl, Mode::Recent as u32).as_str(),
+            "Switch to Recent mode to rerun recently used bookmarks.",
+            "*",
+            "False",
+        ]);
+        table = table.add_row(vec![
+            format!("{:?} {}", KeyModifier::Ctrl, Mode::Diagnostics as u32).as_str(),
+            "Switch to Diagnostics mode to review config problems.",
+            "*",
+            "False",
+        ]);
 
         // Configurable
         table = table.add_row(vec![
@@ -98,6 +261,12 @@ impl State {
             "*",
             "True",
         ]);
+        table = table.add_row(vec![
+            self.keybindings.reload_soft.to_string().as_str(),
+            "Reload bookmarks, keeping the mode, filter and selection.",
+            "*",
+            "True",
+        ]);
         table = table.add_row(vec![
             self.keybindings.switch_filter_label.to_string().as_str(),
             "Switch to label filtering mode.",
@@ -112,19 +281,190 @@ impl State {
         ]);
         table = table.add_row(vec![
             self.keybindings.describe.to_string().as_str(),
-            "Show the description of the selected bookmark.",
+            "Show the details of the selected bookmark.",
             Mode::Bookmarks.to_string().as_str(),
             "True",
         ]);
+        table = table.add_row(vec![
+            self.keybindings.shortcut.to_string().as_str(),
+            "Run the bookmark whose shortcut key is pressed next.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.view_cmds.to_string().as_str(),
+            "Show the commands of the bookmarks in place of their names.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.switch_filter_fuzzy.to_string().as_str(),
+            "Toggle fuzzy matching for name filtering.",
+            "*",
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.switch_filter_regex.to_string().as_str(),
+            "Switch to regex filtering mode.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.switch_filter_all.to_string().as_str(),
+            "Search names, descriptions and labels at once.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.run_in_pane.to_string().as_str(),
+            "Run the selected bookmark in a new pane.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.create.to_string().as_str(),
+            "Create a new bookmark in the main config file.",
+            "*",
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.delete.to_string().as_str(),
+            "Delete the selected bookmark after confirmation.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.preview.to_string().as_str(),
+            "Toggle a preview of the expanded command of the selected bookmark.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.copy.to_string().as_str(),
+            "Copy the expanded command of the selected bookmark to the clipboard.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.export.to_string().as_str(),
+            "Export the listed bookmarks to the export file.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.import.to_string().as_str(),
+            "Import the bookmarks of the import file into the main config file.",
+            "*",
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.append_args.to_string().as_str(),
+            "Type arguments to append to the selected bookmark before running it.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.dump_commands.to_string().as_str(),
+            "Write the expanded command of every bookmark to a JSON lines file.",
+            "*",
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.pin.to_string().as_str(),
+            "Pin or unpin the selected bookmark.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.toggle_case.to_string().as_str(),
+            "Toggle case-sensitive filtering.",
+            "*",
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.clear.to_string().as_str(),
+            "Clear the filter and reset the filter mode.",
+            format!("{}|{}", Mode::Bookmarks, Mode::Labels).as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.duplicate.to_string().as_str(),
+            "Copy the selected bookmark to the main config file.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.mark.to_string().as_str(),
+            "Mark or unmark the selected bookmark to run the marked ones together.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.sort.to_string().as_str(),
+            "Cycle the list order between id, name and label.",
+            format!("{}|{}", Mode::Bookmarks, Mode::Labels).as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.edit_bookmark.to_string().as_str(),
+            "Open the config file of the selected bookmark at its entry.",
+            Mode::Bookmarks.to_string().as_str(),
+            "True",
+        ]);
+        table = table.add_row(vec![
+            self.keybindings.validate.to_string().as_str(),
+            "Validate the config and show all problems.",
+            "*",
+            "True",
+        ]);
 
         print_table_with_coordinates(table, 2, 2, None, None);
     }
 
-    fn render_labels(&self, rows: usize, cols: usize) {
-        let iter = self
-            .labels
+    fn render_diagnostics(&self, rows: usize, cols: usize) {
+        let all_modes: Vec<Mode> = Mode::iter().collect();
+        render_mode(0, 0, Mode::Diagnostics, &all_modes, &self.ui_style);
+
+        if self.diagnostics.is_empty() {
+            print_text_with_coordinates(Text::new("No problems found."), 2, 2, None, None);
+            return;
+        }
+
+        let width = cols.saturating_sub(4);
+        for (i, problem) in self
+            .diagnostics
             .iter()
-            .map(|(index, item)| (index, item.value.id, &item.value.name, item.indices.clone()));
+            .take(rows.saturating_sub(3))
+            .enumerate()
+        {
+            let (line, _) = take_width(problem, width);
+            print_text_with_coordinates(Text::new(line).error_color_all(), 2, 2 + i, None, None);
+        }
+    }
+
+    // Bookmarks are marked with the color of their first label.
+    fn bookmark_color(&self, bookmark: &Bookmark) -> Option<usize> {
+        bookmark
+            .labels
+            .first()
+            .and_then(|label| label_color(label, &self.config.label_colors))
+    }
+
+    fn render_labels(&self, rows: usize, cols: usize) {
+        let separator = self.label_hierarchy_separator();
+        let iter = self.labels.iter().map(|(index, item)| {
+            // Nested labels are indented by their depth.
+            let indent = separator.as_deref().map_or(String::new(), |separator| {
+                "  ".repeat(label_ancestors(&item.value.name, separator).len())
+            });
+            (
+                index,
+                item.value.id,
+                format!("{}{} ({})", indent, item.value.name, item.value.count),
+                item.indices.iter().map(|i| i + indent.len()).collect(),
+                label_color(&item.value.name, &self.config.label_colors),
+            )
+        });
         let all_modes: Vec<Mode> = Mode::iter().collect();
 
         render_main_menu(
@@ -132,20 +472,30 @@ impl State {
             cols,
             self.labels.get_position(),
             self.labels.len(),
+            self.labels.origin_len(),
             Mode::Labels,
             &all_modes,
             &self.ui_style,
+            self.menu_header(),
             self.filter.clone(),
-            self.filter_mode.to_string(),
+            self.filter_by(),
+            self.id_match_len(),
+            false,
+            &HashMap::new(),
             iter,
         );
     }
 
     fn render_edit(&self, rows: usize, cols: usize) {
-        let iter = self
-            .editable_files
-            .iter()
-            .map(|(index, item)| (index, item.value.id, &item.value.path, item.indices.clone()));
+        let iter = self.editable_files.iter().map(|(index, item)| {
+            (
+                index,
+                item.value.id,
+                item.value.path.clone(),
+                item.indices.clone(),
+                None,
+            )
+        });
         let all_modes: Vec<Mode> = Mode::iter().collect();
 
         render_main_menu(
@@ -153,65 +503,556 @@ impl State {
             cols,
             self.editable_files.get_position(),
             self.editable_files.len(),
+            self.editable_files.origin_len(),
             Mode::Edit,
             &all_modes,
             &self.ui_style,
+            self.menu_header(),
             self.filter.clone(),
-            self.filter_mode.to_string(),
+            self.filter_by(),
+            self.id_match_len(),
+            false,
+            &HashMap::new(),
             iter,
         );
     }
 
+    // A header above the first listed bookmark of each group.
+    fn group_headers(&self) -> HashMap<usize, String> {
+        let mut headers = HashMap::new();
+        if !self.group_view {
+            return headers;
+        }
+        let mut previous = None;
+        for (index, item) in self.bookmarks.iter() {
+            let group = item.value.group.as_deref().unwrap_or(UNGROUPED_HEADER);
+            if previous != Some(group) {
+                headers.insert(index, group.to_string());
+                previous = Some(group);
+            }
+        }
+        headers
+    }
+
     fn render_bookmarks(&self, rows: usize, cols: usize) {
-        let iter = self.bookmarks.iter().map(|(index, item)| {
-            if self.view_desc {
-                (index, item.value.id, &item.value.desc, item.indices.clone())
+        let two_columns = !self.view_desc && !self.view_cmds && cols >= TWO_COLUMN_MIN_COLS;
+        let name_width = cols / 2 - 8;
+        let iter = self.bookmarks.iter().map(move |(index, item)| {
+            let mut value = if self.view_desc {
+                item.value.desc.clone()
+            } else if self.view_cmds {
+                item.value.cmd_summary()
             } else {
-                (index, item.value.id, &item.value.name, item.indices.clone())
+                item.value.name.clone()
+            };
+            if let Some(key) = item.value.key {
+                value.push_str(&format!(" [{}]", key));
+            }
+            if self.matched_description_only(&item.indices) {
+                value.push_str(DESCRIPTION_MATCH_MARKER);
+            }
+            if self.modified_bookmarks.contains(&item.value.name) {
+                value.push_str(MODIFIED_MARKER);
+            }
+            if item.value.pinned {
+                value.push_str(PINNED_MARKER);
             }
+            match self.history.exit_code(&item.value.name) {
+                Some(0) => value.push_str(EXIT_SUCCESS_MARKER),
+                Some(code) => value.push_str(&format!("{} {}", EXIT_FAILURE_MARKER, code)),
+                None => {}
+            }
+            // Marked bookmarks show their place in the run order.
+            if let Some(position) = self.marked.iter().position(|m| m == &item.value.name) {
+                value.push_str(&format!(" [{}{}]", MARKED_MARKER, position + 1));
+            }
+            // Matches are found in names, so commands aren't highlighted.
+            let mut indices = if self.view_cmds {
+                Vec::new()
+            } else {
+                item.indices.clone()
+            };
+            if let Some(icon) = self.config.icon(&item.value) {
+                value = format!("{} {}", icon, value);
+                let shift = icon.chars().count() + 1;
+                indices.iter_mut().for_each(|i| *i += shift);
+            }
+            if two_columns {
+                let (name, visible) = fit_column(&value, name_width);
+                indices.retain(|&i| i < visible);
+                value = format!("{} {}", name, item.value.desc.lines().next().unwrap_or(""));
+            }
+            let exec_marker = if self.executes(&item.value) {
+                EXEC_MARKER
+            } else {
+                PASTE_MARKER
+            };
+            let shift = exec_marker.chars().count();
+            (
+                index,
+                item.value.id,
+                format!("{}{}", exec_marker, value),
+                indices.into_iter().map(|i| i + shift).collect(),
+                self.bookmark_color(&item.value),
+            )
         });
+        let headers = self.group_headers();
         let all_modes: Vec<Mode> = Mode::iter().collect();
         render_main_menu(
             rows,
             cols,
             self.bookmarks.get_position(),
-            self.bookmarks.len(),
+            self.bookmarks.total_len(),
+            self.bookmarks.origin_len(),
             Mode::Bookmarks,
             &all_modes,
             &self.ui_style,
+            self.menu_header(),
+            self.filter.clone(),
+            self.filter_by(),
+            self.id_match_len(),
+            self.view_desc && self.wrap_description,
+            &headers,
+            iter,
+        );
+        render_suggestions(
+            rows,
+            cols,
+            self.menu_header(),
+            &self.label_suggestions(),
+            self.suggestion,
+            LABEL_SUGGESTIONS_SHOWN,
+            &self.ui_style,
+        );
+    }
+
+    fn render_workflows(&self, rows: usize, cols: usize) {
+        let iter = self.workflows.iter().map(|(index, item)| {
+            (
+                index,
+                item.value.id,
+                item.value.summary(),
+                item.indices.clone(),
+                None,
+            )
+        });
+        let all_modes: Vec<Mode> = Mode::iter().collect();
+
+        render_main_menu(
+            rows,
+            cols,
+            self.workflows.get_position(),
+            self.workflows.len(),
+            self.workflows.origin_len(),
+            Mode::Workflows,
+            &all_modes,
+            &self.ui_style,
+            self.menu_header(),
+            self.filter.clone(),
+            self.filter_by(),
+            self.id_match_len(),
+            false,
+            &HashMap::new(),
+            iter,
+        );
+    }
+
+    fn render_recent(&self, rows: usize, cols: usize) {
+        let iter = self.recent.iter().map(|(index, item)| {
+            (
+                index,
+                item.value.id,
+                item.value.name.clone(),
+                item.indices.clone(),
+                self.bookmark_color(&item.value),
+            )
+        });
+        let all_modes: Vec<Mode> = Mode::iter().collect();
+
+        render_main_menu(
+            rows,
+            cols,
+            self.recent.get_position(),
+            self.recent.total_len(),
+            self.recent.origin_len(),
+            Mode::Recent,
+            &all_modes,
+            &self.ui_style,
+            self.menu_header(),
+            self.filter.clone(),
+            self.filter_by(),
+            self.id_match_len(),
+            false,
+            &HashMap::new(),
+            iter,
+        );
+    }
+
+    fn render_describe(&self, rows: usize, cols: usize) {
+        let iter = self.bookmarks.iter().map(|(index, item)| {
+            (
+                index,
+                item.value.id,
+                item.value.name.clone(),
+                item.value.desc.clone(),
+                item.indices.clone(),
+            )
+        });
+        let all_modes: Vec<Mode> = Mode::iter().collect();
+        render_describe_menu(
+            rows,
+            cols,
+            self.bookmarks.get_position(),
+            self.bookmarks.total_len(),
+            self.bookmarks.origin_len(),
+            Mode::Describe,
+            &all_modes,
+            &self.ui_style,
+            self.menu_header(),
             self.filter.clone(),
-            self.filter_mode.to_string(),
+            self.filter_by(),
+            self.id_match_len(),
             iter,
         );
     }
 
+    // The most important keys, so Usage mode is not the only way to find them.
+    fn render_footer(&self, rows: usize, cols: usize) {
+        let modes = format!(
+            "{}{}",
+            self.keybindings.prev_mode, self.keybindings.next_mode
+        );
+        let hints = [
+            (self.keybindings.run.to_string(), "run"),
+            (modes, "mode"),
+            (self.keybindings.edit.to_string(), "edit"),
+            (self.keybindings.reload.to_string(), "reload"),
+            (format!("Ctrl {}", Mode::Usage as u32), "help"),
+        ];
+        render_footer(
+            2,
+            rows.saturating_sub(1),
+            cols.saturating_sub(4),
+            &hints,
+            &self.ui_style,
+        );
+    }
+
+    fn render_preview(&self, preview: Result<String, String>, y: usize, rows: usize, cols: usize) {
+        print_text_with_coordinates(
+            Text::new("Preview:").color_range(self.ui_style.chrome_color, ..),
+            2,
+            y,
+            None,
+            None,
+        );
+
+        let (lines, is_error) = match preview {
+            Ok(cmd) => (cmd, false),
+            Err(err) => (err, true),
+        };
+        let width = cols.saturating_sub(4);
+        for (i, line) in lines.lines().take(rows.saturating_sub(1)).enumerate() {
+            let (line, _) = take_width(line, width);
+            let mut text = Text::new(line);
+            if is_error {
+                text = text.error_color_all();
+            }
+            print_text_with_coordinates(text, 2, y + 1 + i, None, None);
+        }
+    }
+
+    fn render_prompt(&self, prompt: &VarPrompt) {
+        let Some(var) = prompt.current() else {
+            return;
+        };
+
+        let title = format!("Run '{}'", prompt.bookmark.name);
+        print_text_with_coordinates(
+            Text::new(title).color_range(self.ui_style.chrome_color, ..),
+            2,
+            1,
+            None,
+            None,
+        );
+
+        let input = format!("{}: {}_", var, prompt.input);
+        let var_len = var.chars().count();
+        print_text_with_coordinates(
+            Text::new(input).color_range(self.ui_style.match_color(), ..var_len),
+            2,
+            3,
+            None,
+            None,
+        );
+
+        let hint = format!("{} confirm, {} cancel", BareKey::Enter, BareKey::Esc);
+        print_text_with_coordinates(
+            Text::new(hint).color_range(self.ui_style.chrome_color, ..),
+            2,
+            5,
+            None,
+            None,
+        );
+    }
+
+    fn render_append_args(&self, args: &str) {
+        let Some(bookmark) = self.bookmarks.get_selected() else {
+            return;
+        };
+
+        let title = format!("Append to '{}'", bookmark.name);
+        print_text_with_coordinates(
+            Text::new(title).color_range(self.ui_style.chrome_color, ..),
+            2,
+            1,
+            None,
+            None,
+        );
+
+        let label = "Args";
+        let input = format!("{}: {}_", label, args);
+        print_text_with_coordinates(
+            Text::new(input).color_range(self.ui_style.match_color(), ..label.len()),
+            2,
+            3,
+            None,
+            None,
+        );
+
+        let hint = format!("{} run, {} cancel", BareKey::Enter, BareKey::Esc);
+        print_text_with_coordinates(
+            Text::new(hint).color_range(self.ui_style.chrome_color, ..),
+            2,
+            5,
+            None,
+            None,
+        );
+    }
+
+    fn render_form(&self, form: &BookmarkForm) {
+        let field = |label: &str, value: &str, active: bool| {
+            let value = if active {
+                format!("{}_", form.input)
+            } else {
+                value.to_string()
+            };
+            let mut text = Text::new(format!("{}: {}", label, value));
+            if active {
+                text = text.color_range(self.ui_style.match_color(), ..label.chars().count());
+            }
+            text
+        };
+
+        print_text_with_coordinates(
+            Text::new("Create bookmark").color_range(self.ui_style.chrome_color, ..),
+            2,
+            1,
+            None,
+            None,
+        );
+        print_text_with_coordinates(
+            field("Name", &form.name, form.field == FormField::Name),
+            2,
+            3,
+            None,
+            None,
+        );
+        print_text_with_coordinates(
+            field("Description", &form.desc, form.field == FormField::Desc),
+            2,
+            4,
+            None,
+            None,
+        );
+        print_text_with_coordinates(Text::new("Commands:"), 2, 5, None, None);
+
+        let mut y = 6;
+        for (i, cmd) in form.cmds.iter().enumerate() {
+            print_text_with_coordinates(Text::new(format!("{}. {}", i + 1, cmd)), 4, y, None, None);
+            y += 1;
+        }
+        if form.field == FormField::Cmds {
+            let text = Text::new(format!("> {}_", form.input))
+                .color_range(self.ui_style.match_color(), ..1);
+            print_text_with_coordinates(text, 4, y, None, None);
+            y += 1;
+        }
+
+        if let Some(error) = form.error.as_ref() {
+            print_text_with_coordinates(Text::new(error).error_color_all(), 2, y + 1, None, None);
+            y += 1;
+        }
+
+        let hint = format!(
+            "{} next field or add command, {} on empty command saves, {} cancel",
+            BareKey::Enter,
+            BareKey::Enter,
+            BareKey::Esc
+        );
+        print_text_with_coordinates(
+            Text::new(hint).color_range(self.ui_style.chrome_color, ..),
+            2,
+            y + 2,
+            None,
+            None,
+        );
+    }
+
+    // A single line for panes too small for the full layout, e.g. while resizing.
+    fn compact_line(&self, cols: usize) -> String {
+        let mut line = format!("{}: {}", self.mode, self.filter);
+        if let Some(name) = self.selected_name() {
+            line.push_str(&format!(" > {}", name));
+        }
+        take_width(&line, cols).0
+    }
+
+    // The notice line is only reserved while something is drawn on it, see
+    // the end of `render`.
+    fn menu_header(&self) -> MenuHeader {
+        MenuHeader {
+            notice: self.notice.is_some()
+                || self.dry_run
+                || self.confirm_run.is_some()
+                || (self.confirm_delete.is_some() && self.mode == Mode::Bookmarks),
+        }
+    }
+
     pub(crate) fn render(&mut self, rows: usize, cols: usize) {
-        if rows < RESERVE_ROW_COUNT || cols < RESERVE_COLUMN_COUNT {
-            eprintln!(
-                "\
-                ERROR: Rendering failed. The panel is too small.\
-                The number of rows must be more than {}, but it's '{}.\
-                The number of columns must be more than {}, but it's '{}",
-                RESERVE_ROW_COUNT, rows, RESERVE_COLUMN_COUNT, cols
+        self.pane_size = (rows, cols);
+        let header = self.menu_header();
+        // The footer takes the last line, below the menu.
+        let menu_rows = rows.saturating_sub(usize::from(self.show_footer));
+        if menu_rows < header.reserve_rows() || cols < RESERVE_COLUMN_COUNT {
+            // Nothing fits, so wait for the pane to grow again.
+            if rows == 0 || cols == 0 {
+                return;
+            }
+            print_text_with_coordinates(
+                Text::new(self.compact_line(cols)).color_range(self.ui_style.chrome_color, ..),
+                0,
+                0,
+                None,
+                None,
             );
-            close_focus()
+            return;
         }
         if self.error_mgr.render() {
             return;
         }
+        self.page_size = menu_height(menu_rows, header).max(1);
+        if let Some(prompt) = self.prompt.as_ref() {
+            self.render_prompt(prompt);
+            return;
+        }
+        if let Some(form) = self.form.as_ref() {
+            self.render_form(form);
+            return;
+        }
+        if let Some(args) = self.append_args.as_ref() {
+            self.render_append_args(args);
+            return;
+        }
         match self.mode {
             Mode::Bookmarks => {
-                self.render_bookmarks(rows, cols);
+                let preview = match self.bookmarks.get_selected() {
+                    Some(bookmark) if self.preview => Some(self.gen_preview(bookmark)),
+                    _ => None,
+                };
+                // The preview takes the lower half of the pane, below a blank
+                // line. It is hidden while that would leave the menu less than
+                // its reserved rows.
+                let list_rows = (rows - rows / 2).max(header.reserve_rows());
+                let preview = preview.filter(|_| menu_rows >= list_rows + 1 + PREVIEW_MIN_ROWS);
+                let list_rows = match preview {
+                    Some(_) => list_rows,
+                    None => menu_rows,
+                };
+                self.page_size = menu_height(list_rows, header).max(1);
+                self.render_bookmarks(list_rows, cols);
+                if let Some(bookmark) = self.bookmarks.get_selected().filter(|_| self.show_details)
+                {
+                    render_popup(
+                        list_rows,
+                        cols,
+                        header,
+                        &bookmark.name,
+                        &bookmark.details(),
+                        &self.ui_style,
+                    );
+                }
+                if let Some(preview) = preview {
+                    // Ends above the footer.
+                    let preview_rows = menu_rows - list_rows - 1;
+                    self.render_preview(preview, list_rows + 1, preview_rows, cols);
+                }
+                if let Some(bookmark) = self.confirm_delete.as_ref() {
+                    let text = format!("Delete '{}'? (y/n)", bookmark.name);
+                    print_text_with_coordinates(
+                        Text::new(text).error_color_all().opaque(),
+                        2,
+                        1,
+                        None,
+                        None,
+                    );
+                }
             }
             Mode::Labels => {
-                self.render_labels(rows, cols);
+                self.render_labels(menu_rows, cols);
             }
             Mode::Usage => {
                 self.render_usage();
             }
             Mode::Edit => {
-                self.render_edit(rows, cols);
+                self.render_edit(menu_rows, cols);
+            }
+            Mode::Describe => {
+                self.render_describe(menu_rows, cols);
+            }
+            Mode::Workflows => {
+                self.render_workflows(menu_rows, cols);
             }
+            Mode::Recent => {
+                self.render_recent(menu_rows, cols);
+            }
+            Mode::Diagnostics => {
+                self.render_diagnostics(menu_rows, cols);
+            }
+        }
+        if self.show_footer {
+            self.render_footer(rows, cols);
+        }
+        if let Some(run) = self.confirm_run.as_ref() {
+            let text = format!("Run '{}'? (y/n)", run.bookmark.name);
+            print_text_with_coordinates(
+                Text::new(text).error_color_all().opaque(),
+                2,
+                1,
+                None,
+                None,
+            );
+            return;
+        }
+        if self.dry_run {
+            let text = Text::new(DRY_RUN_MARKER).error_color_all();
+            let x = cols.saturating_sub(DRY_RUN_MARKER.len() + 2);
+            print_text_with_coordinates(text, x, 1, None, None);
+        }
+        if let Some(notice) = self
+            .notice
+            .as_ref()
+            .filter(|_| self.confirm_delete.is_none())
+        {
+            print_text_with_coordinates(
+                Text::new(notice)
+                    .color_range(self.ui_style.chrome_color, ..)
+                    .opaque(),
+                2,
+                1,
+                None,
+                None,
+            );
         }
     }
 }
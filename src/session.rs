@@ -0,0 +1,15 @@
+use crate::core::FilterMode;
+use serde::{Deserialize, Serialize};
+
+// What is on screen. Saved when the plugin is closed to be restored on the
+// next load, and on navigation to go back to.
+#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct Session {
+    pub mode: u32,
+    #[serde(default)]
+    pub filter: String,
+    #[serde(default)]
+    pub filter_mode: FilterMode,
+    #[serde(default)]
+    pub position: usize,
+}
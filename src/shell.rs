@@ -0,0 +1,102 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+
+// POSIX-safe quoting: the value is wrapped in single quotes and embedded
+// single quotes are closed, escaped and reopened.
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+// Quotes a path for `cd`. A leading `~` is left outside the quotes so the
+// shell still expands it.
+pub(crate) fn shell_quote_path(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => format!("~/{}", shell_quote(rest)),
+        None if path == "~" => path.to_string(),
+        None => shell_quote(path),
+    }
+}
+
+// `{{shq name}}` expands to the shell-quoted value of a variable.
+pub(crate) struct ShellQuoteHelper;
+
+impl HelperDef for ShellQuoteHelper {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = h
+            .param(0)
+            .map(|param| param.value())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("shq", 0))?;
+        let value = match value.as_str() {
+            Some(value) => value.to_string(),
+            None if value.is_null() => String::new(),
+            None => value.to_string(),
+        };
+        out.write(&shell_quote(&value))?;
+        Ok(())
+    }
+}
+
+// Wraps the command so it is run by the given interpreter. The POSIX shell the
+// line is typed into or run with parses it first, so it is quoted for that
+// shell whichever interpreter it is meant for.
+pub(crate) fn wrap_command(shell: &str, cmd: &str) -> String {
+    format!("{} -c {}", shell, shell_quote(cmd))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{shell_quote, shell_quote_path, wrap_command, ShellQuoteHelper};
+    use handlebars::Handlebars;
+    use std::collections::HashMap;
+
+    #[test]
+    fn shell_quote_escapes_embedded_single_quotes() {
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's; rm -rf"), "'it'\\''s; rm -rf'");
+    }
+
+    #[test]
+    fn shell_quote_path_keeps_the_home_prefix_expandable() {
+        assert_eq!(shell_quote_path("my app"), "'my app'");
+        assert_eq!(shell_quote_path("~/my app"), "~/'my app'");
+        assert_eq!(shell_quote_path("~"), "~");
+        assert_eq!(shell_quote_path("~user/app"), "'~user/app'");
+    }
+
+    #[test]
+    fn shq_helper_quotes_the_variable_value() {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_helper("shq", Box::new(ShellQuoteHelper));
+        let vars = HashMap::from([("msg".to_string(), "hello world".to_string())]);
+
+        let rendered = handlebars
+            .render_template("echo {{shq msg}} {{msg}}", &vars)
+            .unwrap();
+
+        assert_eq!(rendered, "echo 'hello world' hello world");
+    }
+
+    #[test]
+    fn wrap_command_quotes_for_the_outer_shell() {
+        assert_eq!(
+            wrap_command("bash", "echo 'hi' && ls"),
+            "bash -c 'echo '\\''hi'\\'' && ls'"
+        );
+        assert_eq!(
+            wrap_command("/usr/bin/fish", "echo 'a\\b'"),
+            "/usr/bin/fish -c 'echo '\\''a\\b'\\'''"
+        );
+        assert_eq!(
+            wrap_command("pwsh", "Write-Host 'hi'"),
+            "pwsh -c 'Write-Host '\\''hi'\\'''"
+        );
+    }
+}
@@ -1,60 +1,532 @@
 use super::{bookmark, Mode, Navigation, State};
-use crate::bookmark::Bookmark;
-use crate::core::{Filter, FilterMode, GenericFilter};
+use crate::bookmark::{Bookmark, RunTarget, NOTE_PREFIX};
+use crate::core::{
+    complete_label_filter, fill_label_filter, label_suggestions, Filter, FilterMode, GenericFilter,
+    ThemeColors, ID_RANGE_SEPARATOR,
+};
 use crate::editable_file::EditableFile;
+use crate::env::{parse_env, EnvHelper};
+use crate::form::BookmarkForm;
 use crate::label::Label;
-use handlebars::Handlebars;
-use std::collections::HashSet;
+use crate::load::ListOrder;
+use crate::pipe::PipeCommand;
+use crate::prompt::{PendingRun, VarPrompt};
+use crate::session::Session;
+use crate::shell::{shell_quote, shell_quote_path, wrap_command, ShellQuoteHelper};
+use crate::workflow::{Workflow, WorkflowRun, WorkflowStep};
+use handlebars::{no_escape, Handlebars, RenderErrorReason};
+use std::cell::RefMut;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 use zellij_tile::prelude::*;
 
+const CONTEXT_WORKFLOW: &str = "workflow";
+const CONTEXT_WORKFLOW_STEP: &str = "workflow_step";
+const CONTEXT_BOOKMARK: &str = "bookmark";
+const MODIFIED_MARKER_TIMEOUT: f64 = 5.0;
+// Views remembered to go back to.
+const BACK_STACK_LIMIT: usize = 50;
+const CONTEXT_SOURCE: &str = "source";
+const CONTEXT_SOURCE_ENV: &str = "env";
+// Swaps the `sh -c` of a command pane for the user's own shell.
+const INTERACTIVE_SHELL: &str = "exec \"${SHELL:-sh}\"";
+
+pub(crate) struct Renderers {
+    // Renders commands, in strict mode to report missing variables.
+    commands: Handlebars<'static>,
+    // Expands vars that reference other vars, never escaping their values.
+    vars: Handlebars<'static>,
+    // Renders env values and cwds like commands, but never escaping them, as
+    // they are quoted or used as a path afterwards.
+    values: Handlebars<'static>,
+}
+
+impl Renderers {
+    fn new(env: &HashMap<String, String>, quote_vars: bool) -> Self {
+        let mut commands = Handlebars::new();
+        commands.register_helper("env", Box::new(EnvHelper { env: env.clone() }));
+        commands.register_helper("shq", Box::new(ShellQuoteHelper));
+        if quote_vars {
+            commands.register_escape_fn(shell_quote);
+        }
+        commands.set_strict_mode(true);
+
+        let mut vars = Handlebars::new();
+        vars.register_helper("env", Box::new(EnvHelper { env: env.clone() }));
+        vars.register_escape_fn(no_escape);
+
+        let mut values = Handlebars::new();
+        values.register_helper("env", Box::new(EnvHelper { env: env.clone() }));
+        values.register_helper("shq", Box::new(ShellQuoteHelper));
+        values.register_escape_fn(no_escape);
+        values.set_strict_mode(true);
+
+        Self {
+            commands,
+            vars,
+            values,
+        }
+    }
+}
+
+fn shell_command(cmd: String) -> CommandToRun {
+    CommandToRun {
+        path: "sh".into(),
+        args: vec!["-c".to_string(), cmd],
+        cwd: None,
+    }
+}
+
+fn is_reference(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with("cmd::") || line.starts_with("bookmark::")
+}
+
+// Commands that render empty, like an unset optional var, are dropped, and a
+// trailing line continuation is cut, so joining never leaves a separator or
+// a `\` with nothing after it.
+fn clean_command(cmd: &str) -> Option<String> {
+    let cmd = cmd.trim().trim_end_matches('\\').trim_end();
+    (!cmd.is_empty()).then(|| cmd.to_string())
+}
+
+// Content size of the plugin's own pane, in the rows and columns render gets.
+fn plugin_pane_size(manifest: &PaneManifest, plugin_id: u32) -> Option<(usize, usize)> {
+    manifest
+        .panes
+        .values()
+        .flatten()
+        .find(|pane| pane.is_plugin && pane.id == plugin_id)
+        .map(|pane| (pane.pane_content_rows, pane.pane_content_columns))
+}
+
 impl State {
     fn bookmark_filter(&self) -> Box<dyn Filter<Bookmark>> {
-        Box::new(GenericFilter::new(
-            self.filter_mode,
-            self.filter.clone(),
-            self.ignore_case,
-            self.fuzzy_search,
-        ))
+        Box::new(
+            GenericFilter::new(
+                self.filter_mode,
+                self.filter.clone(),
+                self.ignore_case,
+                self.fuzzy_search,
+            )
+            .with_label_match_all(self.label_match_all)
+            .with_id_match(self.id_match)
+            .with_acronyms(self.acronym_search)
+            .with_label_hierarchy(self.label_hierarchy_separator())
+            .with_search_description(self.search_description),
+        )
+    }
+
+    pub(crate) fn label_hierarchy_separator(&self) -> Option<String> {
+        self.hierarchical_labels
+            .then(|| self.label_separator.clone())
     }
 
     fn label_filter(&self) -> Box<dyn Filter<Label>> {
-        Box::new(GenericFilter::new(
-            self.filter_mode,
-            self.filter.clone(),
-            self.ignore_case,
-            self.fuzzy_search,
-        ))
+        Box::new(
+            GenericFilter::new(
+                self.filter_mode,
+                self.filter.clone(),
+                self.ignore_case,
+                self.fuzzy_search,
+            )
+            .with_id_match(self.id_match),
+        )
     }
 
     fn editable_file_filter(&self) -> Box<dyn Filter<EditableFile>> {
-        Box::new(GenericFilter::new(
-            self.filter_mode,
-            self.filter.clone(),
-            self.ignore_case,
-            self.fuzzy_search,
-        ))
+        Box::new(
+            GenericFilter::new(
+                self.filter_mode,
+                self.filter.clone(),
+                self.ignore_case,
+                self.fuzzy_search,
+            )
+            .with_id_match(self.id_match),
+        )
+    }
+
+    fn workflow_filter(&self) -> Box<dyn Filter<Workflow>> {
+        Box::new(
+            GenericFilter::new(
+                self.filter_mode,
+                self.filter.clone(),
+                self.ignore_case,
+                self.fuzzy_search,
+            )
+            .with_id_match(self.id_match),
+        )
     }
 
     fn set_filter(&mut self) {
+        self.suggestion = None;
         match self.mode {
-            Mode::Bookmarks => self.bookmarks.with_filter(self.bookmark_filter()),
+            Mode::Bookmarks | Mode::Describe => {
+                self.bookmarks.with_filter(self.bookmark_filter());
+                self.pin_bookmarks();
+                self.group_bookmarks();
+            }
             Mode::Labels => self.labels.with_filter(self.label_filter()),
             Mode::Edit => self.editable_files.with_filter(self.editable_file_filter()),
+            Mode::Workflows => self.workflows.with_filter(self.workflow_filter()),
+            Mode::Recent => self.recent.with_filter(self.bookmark_filter()),
             _ => {}
         }
     }
 
+    // Bookmarks without labels go last when ordered by label.
+    pub(crate) fn apply_list_order(&mut self) {
+        match self.list_order {
+            ListOrder::Id => {
+                self.bookmarks.sort_by_key(|b| b.index);
+                self.labels.sort_by_key(|l| l.id);
+            }
+            ListOrder::Name => {
+                self.bookmarks.sort_by_key(|b| b.name.clone());
+                self.labels.sort_by_key(|l| l.name.clone());
+            }
+            ListOrder::Label => {
+                self.bookmarks.sort_by_key(|b| {
+                    (
+                        b.labels.is_empty(),
+                        b.labels.first().cloned(),
+                        b.name.clone(),
+                    )
+                });
+                self.labels.sort_by_key(|l| l.name.clone());
+            }
+        }
+        self.pin_bookmarks();
+        self.group_bookmarks();
+    }
+
+    // Pinned bookmarks stay on top, in the order they are otherwise listed in.
+    fn pin_bookmarks(&mut self) {
+        self.bookmarks.sort_by_key(|b| !b.pinned);
+    }
+
+    // Groups are listed by name with the ungrouped bookmarks last. Within a
+    // group the bookmarks keep their order.
+    fn group_bookmarks(&mut self) {
+        if self.group_view {
+            self.bookmarks
+                .sort_by_key(|b| (b.group.is_none(), b.group.clone()));
+        }
+    }
+
+    // Every label of the config, for completing the label filter.
+    pub(crate) fn all_labels(&self) -> Vec<String> {
+        self.config
+            .bookmarks
+            .iter()
+            .flat_map(|bookmark| bookmark.labels.iter().cloned())
+            .collect()
+    }
+
+    // The label filter with the label being typed completed, so Tab completes
+    // instead of moving the selection when there is something to complete.
+    fn label_completion(&self) -> Option<String> {
+        if self.filter_mode != FilterMode::Label
+            || !matches!(self.mode, Mode::Bookmarks | Mode::Describe | Mode::Recent)
+        {
+            return None;
+        }
+        complete_label_filter(&self.filter, &self.all_labels(), self.ignore_case)
+    }
+
+    pub(crate) fn label_suggestions(&self) -> Vec<String> {
+        if self.filter_mode != FilterMode::Label || self.mode != Mode::Bookmarks {
+            return Vec::new();
+        }
+        label_suggestions(&self.filter, &self.all_labels(), self.ignore_case)
+    }
+
+    // Tab and Shift Tab walk the suggestions, wrapping at both ends.
+    fn select_suggestion(&mut self, back: bool) {
+        let count = self.label_suggestions().len();
+        if count == 0 {
+            return;
+        }
+        self.suggestion = Some(match (self.suggestion, back) {
+            (None, false) => 0,
+            (None, true) => count - 1,
+            (Some(i), false) => (i + 1) % count,
+            (Some(i), true) => (i + count - 1) % count,
+        });
+    }
+
+    fn fill_suggestion(&mut self) {
+        let suggestions = self.label_suggestions();
+        if let Some(label) = self.suggestion.and_then(|i| suggestions.get(i)) {
+            self.filter = fill_label_filter(&self.filter, label);
+            self.set_filter();
+        }
+    }
+
+    // With vim keys, hjkl navigate until a search is started with '/'.
+    fn vim_key(&self, c: char) -> Option<KeyWithModifier> {
+        if !self.vim_keys || self.vim_search || !self.filter.is_empty() {
+            return None;
+        }
+        let binding = match c {
+            'j' => &self.keybindings.down,
+            'k' => &self.keybindings.up,
+            'h' => &self.keybindings.prev_mode,
+            'l' => &self.keybindings.next_mode,
+            _ => return None,
+        };
+        Some(binding.key().clone())
+    }
+
+    // Shortcut keys follow the shortcut binding, so they never take a key
+    // away from the filter.
+    fn shortcut_bookmark(&self, c: char) -> Option<Bookmark> {
+        if self.mode != Mode::Bookmarks {
+            return None;
+        }
+        self.config
+            .bookmarks
+            .iter()
+            .find(|bookmark| bookmark.key == Some(c))
+            .cloned()
+    }
+
     fn reset_selection(&mut self) {
         self.bookmarks.reset_selection();
         self.labels.reset_selection();
         self.editable_files.reset_selection();
+        self.workflows.reset_selection();
+        self.recent.reset_selection();
+    }
+
+    fn position(&self) -> usize {
+        match self.mode {
+            Mode::Bookmarks | Mode::Describe => self.bookmarks.get_position(),
+            Mode::Labels => self.labels.get_position(),
+            Mode::Edit => self.editable_files.get_position(),
+            Mode::Workflows => self.workflows.get_position(),
+            Mode::Recent => self.recent.get_position(),
+            Mode::Usage | Mode::Diagnostics => 0,
+        }
+    }
+
+    pub(crate) fn selected_name(&self) -> Option<String> {
+        match self.mode {
+            Mode::Bookmarks | Mode::Describe => {
+                self.bookmarks.get_selected().map(|b| b.name.clone())
+            }
+            Mode::Labels => self.labels.get_selected().map(|l| l.name.clone()),
+            Mode::Edit => self.editable_files.get_selected().map(|f| f.path.clone()),
+            Mode::Workflows => self.workflows.get_selected().map(|w| w.name.clone()),
+            Mode::Recent => self.recent.get_selected().map(|b| b.name.clone()),
+            Mode::Usage | Mode::Diagnostics => None,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self.mode {
+            Mode::Bookmarks | Mode::Describe => self.bookmarks.len(),
+            Mode::Labels => self.labels.len(),
+            Mode::Edit => self.editable_files.len(),
+            Mode::Workflows => self.workflows.len(),
+            Mode::Recent => self.recent.len(),
+            Mode::Usage | Mode::Diagnostics => 0,
+        }
+    }
+
+    fn select(&mut self, position: usize) {
+        match self.mode {
+            Mode::Bookmarks | Mode::Describe => self.bookmarks.select(position),
+            Mode::Labels => self.labels.select(position),
+            Mode::Edit => self.editable_files.select(position),
+            Mode::Workflows => self.workflows.select(position),
+            Mode::Recent => self.recent.select(position),
+            Mode::Usage | Mode::Diagnostics => {}
+        }
+    }
+
+    // Falls back to the position when no item has the name.
+    fn select_named(&mut self, name: &str, position: usize) {
+        match self.mode {
+            Mode::Bookmarks | Mode::Describe => self.bookmarks.select_named(name),
+            Mode::Labels => self.labels.select_named(name),
+            Mode::Edit => self.editable_files.select_named(name),
+            Mode::Workflows => self.workflows.select_named(name),
+            Mode::Recent => self.recent.select_named(name),
+            Mode::Usage | Mode::Diagnostics => {}
+        }
+        if self.selected_name().as_deref() != Some(name) {
+            self.select(position);
+        }
+    }
+
+    fn current_view(&self) -> Session {
+        Session {
+            mode: self.mode as u32,
+            filter: self.filter.clone(),
+            filter_mode: self.filter_mode,
+            position: self.position(),
+        }
+    }
+
+    // Remembers the current view so Backspace on an empty filter returns to it.
+    fn push_view(&mut self) {
+        self.back_stack.push(self.current_view());
+        if self.back_stack.len() > BACK_STACK_LIMIT {
+            self.back_stack.remove(0);
+        }
+    }
+
+    fn go_back(&mut self) -> bool {
+        match self.back_stack.pop() {
+            Some(view) => {
+                self.restore_session(view);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // The filter mode is reset unless the filter is preserved and the new mode
+    // supports it. A filter that can't be carried over is cleared, so e.g. ids
+    // aren't matched against names.
+    fn switch_mode(&mut self, mode: Mode) {
+        self.push_view();
+        self.mode = mode;
+        if !self.preserve_filter_on_mode_switch {
+            self.filter_mode = FilterMode::default();
+        } else if !mode.supports(self.filter_mode) {
+            self.filter_mode = FilterMode::default();
+            self.filter.clear();
+        }
+        self.set_filter();
     }
 
+    // The best match comes first, so narrowing the filter selects the top of
+    // the list for Enter to run it, wherever the selection was before.
+    // Widening it only refilters, which keeps the selected bookmark.
+    fn filter_changed(&mut self) {
+        self.set_filter();
+        self.select(0);
+    }
+
+    // Ids don't start with 0, and a range like "10-20" needs a start before
+    // its single separator.
+    fn accepts_id_char(&self, c: char) -> bool {
+        match c.to_digit(10) {
+            Some(digit) => !self.filter.is_empty() || digit > 0,
+            None => {
+                c == ID_RANGE_SEPARATOR
+                    && !self.filter.is_empty()
+                    && !self.filter.contains(ID_RANGE_SEPARATOR)
+            }
+        }
+    }
+
+    fn clear_filter(&mut self) {
+        self.filter.clear();
+        self.filter_mode = FilterMode::default();
+        self.set_filter();
+        self.reset_selection();
+    }
+
+    fn save_session(&mut self) {
+        let session = self.current_view();
+        if let Err(e) = self.write_session(&session) {
+            self.error_mgr
+                .handle_error(format!("Failed to save session: {}.", e));
+        }
+    }
+
+    // The config may have changed since the session was saved, so a filter
+    // without matches is dropped and the selection is clamped. A filter mode
+    // the saved mode doesn't support falls back to name, like on startup.
+    pub(crate) fn restore_session(&mut self, session: Session) {
+        let Ok(mode) = Mode::try_from(session.mode) else {
+            return;
+        };
+        self.mode = mode;
+        self.filter = session.filter;
+        self.filter_mode = if mode.supports(session.filter_mode) {
+            session.filter_mode
+        } else {
+            FilterMode::default()
+        };
+        self.set_filter();
+
+        if !self.filter.is_empty() && self.len() == 0 {
+            self.filter.clear();
+            self.filter_mode = FilterMode::default();
+            self.set_filter();
+        }
+
+        self.select(session.position);
+    }
+
+    // A filter mode the view does not support falls back to the default one,
+    // and the error reports it.
+    pub(crate) fn set_initial_view(
+        &mut self,
+        mode: Mode,
+        filter: String,
+        filter_mode: FilterMode,
+    ) -> Result<(), String> {
+        let supported = mode.supports(filter_mode);
+        self.mode = mode;
+        self.filter = filter;
+        self.filter_mode = if supported {
+            filter_mode
+        } else {
+            FilterMode::default()
+        };
+        self.set_filter();
+        self.reset_selection();
+        if supported {
+            Ok(())
+        } else {
+            Err(format!(
+                "{filter_mode} filter mode is not supported in {mode} mode"
+            ))
+        }
+    }
+
+    // Rendered commands are cached by bookmark and its vars until the config is
+    // reloaded. Only top-level renders are cached: a dependency served from the
+    // cache would skip the depth and cycle checks of the chain using it, and
+    // the result would depend on what was rendered before.
     fn gen_template_command(
         &self,
         bookmark: bookmark::Bookmark,
         processed: &mut HashSet<String>,
+        depth: usize,
+    ) -> Result<String, String> {
+        if depth > 0 {
+            return self.render_template_command(bookmark, processed, depth);
+        }
+        let key = (bookmark.name.clone(), bookmark.fingerprint());
+        if let Some(cmd) = self.template_cache.borrow().get(&key) {
+            return Ok(cmd.clone());
+        }
+        let cmd = self.render_template_command(bookmark, processed, depth)?;
+        self.template_cache.borrow_mut().insert(key, cmd.clone());
+        Ok(cmd)
+    }
+
+    // Depth counts the `bookmark::` and nested `cmd::` references followed to
+    // get here. It is capped so a long chain can't overflow the stack.
+    fn render_template_command(
+        &self,
+        bookmark: bookmark::Bookmark,
+        processed: &mut HashSet<String>,
+        depth: usize,
     ) -> Result<String, String> {
+        if depth > self.max_template_depth {
+            return Err(format!(
+                "Maximum template depth of {} exceeded at bookmark '{}'",
+                self.max_template_depth, bookmark.name
+            ));
+        }
         let mut cmds: Vec<String> = Vec::new();
         let separator = bookmark
             .separator
@@ -68,36 +540,140 @@ impl State {
             ));
         }
 
+        if let Some(cwd) = self.gen_cwd(&bookmark)? {
+            cmds.push(format!("cd {}", cwd));
+        }
+
         for cmd in bookmark.cmds.iter() {
-            if let Some(dep_bookmark_name) = cmd.strip_prefix("bookmark::") {
-                if let Some(dep_bookmark) = self
-                    .config
-                    .bookmarks
-                    .iter()
-                    .find(|b| b.name == dep_bookmark_name)
-                {
-                    let mut dep_bookmark = dep_bookmark.clone();
-                    dep_bookmark.vars.extend(bookmark.vars.clone());
-                    let cmds_from_dep_bookmark =
-                        self.gen_template_command(dep_bookmark, processed)?;
-                    cmds.push(cmds_from_dep_bookmark);
-                } else {
-                    return Err(format!("Bookmark '{}' not found", dep_bookmark_name));
-                }
-            } else if let Some(cmd_key) = cmd.strip_prefix("cmd::") {
-                if let Some(cmd_value) = self.config.cmds.get(cmd_key) {
-                    let rendered_cmd = self.gen_template_with_vars(cmd_value, &bookmark)?;
-                    cmds.push(rendered_cmd);
-                } else {
-                    return Err(format!("Command key '{}' not found in cmds", cmd_key));
-                }
+            self.expand_command(cmd, &bookmark, processed, depth, &mut Vec::new(), &mut cmds)?;
+        }
+
+        Ok(cmds.join(separator.as_str()))
+    }
+
+    // Resolves one entry of the bookmark's cmds into the commands it stands
+    // for. A `cmd::` value whose lines reference other cmds or bookmarks is
+    // resolved again line by line; `cmd_keys` holds the cmds being resolved
+    // to catch cycles between them.
+    fn expand_command(
+        &self,
+        cmd: &str,
+        bookmark: &Bookmark,
+        processed: &mut HashSet<String>,
+        depth: usize,
+        cmd_keys: &mut Vec<String>,
+        cmds: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if cmd.starts_with(NOTE_PREFIX) {
+            return Ok(());
+        }
+        if let Some(dep_bookmark_name) = cmd.strip_prefix("bookmark::") {
+            if let Some(dep_bookmark) = self
+                .config
+                .bookmarks
+                .iter()
+                .find(|b| b.name == dep_bookmark_name)
+            {
+                let mut dep_bookmark = dep_bookmark.clone();
+                dep_bookmark.vars.extend(bookmark.vars.clone());
+                let cmds_from_dep_bookmark =
+                    self.gen_template_command(dep_bookmark, processed, depth + cmd_keys.len() + 1)?;
+                cmds.extend(clean_command(&cmds_from_dep_bookmark));
             } else {
-                let rendered_cmd = self.gen_template_with_vars(cmd, &bookmark)?;
-                cmds.push(rendered_cmd);
+                return Err(format!("Bookmark '{}' not found", dep_bookmark_name));
+            }
+        } else if let Some(cmd_key) = cmd.strip_prefix("cmd::") {
+            let Some(cmd_value) = self.config.cmds.get(cmd_key) else {
+                return Err(format!("Command key '{}' not found in cmds", cmd_key));
+            };
+            let rendered_cmd = self.gen_template_with_vars(cmd_value, bookmark)?;
+            if !rendered_cmd.lines().any(is_reference) {
+                cmds.extend(clean_command(&rendered_cmd));
+                return Ok(());
+            }
+            if cmd_keys.iter().any(|key| key == cmd_key) {
+                return Err(format!(
+                    "Circular dependency detected for command '{}'",
+                    cmd_key
+                ));
+            }
+            if depth + cmd_keys.len() >= self.max_template_depth {
+                return Err(format!(
+                    "Maximum template depth of {} exceeded at bookmark '{}'",
+                    self.max_template_depth, bookmark.name
+                ));
             }
+            cmd_keys.push(cmd_key.to_string());
+            for line in rendered_cmd.lines() {
+                self.expand_command(line.trim(), bookmark, processed, depth, cmd_keys, cmds)?;
+            }
+            cmd_keys.pop();
+        } else {
+            let rendered_cmd = self.gen_template_with_vars(cmd, bookmark)?;
+            cmds.extend(clean_command(&rendered_cmd));
         }
+        Ok(())
+    }
 
-        Ok(cmds.join(separator.as_str()))
+    // The bookmark's cwd, rendered with its vars and quoted once as a whole.
+    // It is only ever applied by this `cd`, so new panes open in the plugin's
+    // cwd and relative dirs resolve from there like in the current pane.
+    fn gen_cwd(&self, bookmark: &Bookmark) -> Result<Option<String>, String> {
+        bookmark
+            .cwd
+            .as_ref()
+            .map(|cwd| Ok(shell_quote_path(&self.gen_value_with_vars(cwd, bookmark)?)))
+            .transpose()
+    }
+
+    // The Handlebars instances are built on first use, with the helpers and
+    // escaping the settings ask for, and reused for every render after that.
+    fn renderers(&self) -> RefMut<'_, Renderers> {
+        RefMut::map(self.renderers.borrow_mut(), |renderers| {
+            renderers.get_or_insert_with(|| Renderers::new(&self.env, self.quote_vars))
+        })
+    }
+
+    // Expands vars that reference other vars until nothing changes. A chain
+    // of n vars settles within n passes, so anything still changing after
+    // that is a cycle. Values with escaped braces are left alone.
+    fn resolve_vars(&self, vars: &mut HashMap<String, String>) -> Result<(), String> {
+        let handlebars = &self.renderers().vars;
+
+        for _ in 0..=vars.len() {
+            let mut changed = Vec::new();
+            let snapshot = vars.clone();
+            for (name, value) in vars.iter_mut() {
+                if !value.contains("{{") || value.contains("\\{{") {
+                    continue;
+                }
+                let rendered = handlebars
+                    .render_template(value, &snapshot)
+                    .map_err(|e| format!("Failed to expand var '{}': {}", name, e))?;
+                if rendered != *value {
+                    *value = rendered;
+                    changed.push(name.clone());
+                }
+            }
+            if changed.is_empty() {
+                return Ok(());
+            }
+        }
+
+        let mut cyclic: Vec<&String> = vars
+            .iter()
+            .filter(|(_, value)| value.contains("{{"))
+            .map(|(name, _)| name)
+            .collect();
+        cyclic.sort();
+        Err(format!(
+            "Vars reference each other in a cycle: {}",
+            cyclic
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
     }
 
     fn gen_template_with_vars(
@@ -105,299 +681,2202 @@ impl State {
         template: &str,
         bookmark: &Bookmark,
     ) -> Result<String, String> {
-        let handlebars = Handlebars::new();
-        let mut vars = self.config.vars.clone();
+        self.render_with_vars(template, bookmark, |renderers| &mut renderers.commands)
+    }
+
+    fn gen_value_with_vars(&self, template: &str, bookmark: &Bookmark) -> Result<String, String> {
+        self.render_with_vars(template, bookmark, |renderers| &mut renderers.values)
+    }
+
+    fn render_with_vars(
+        &self,
+        template: &str,
+        bookmark: &Bookmark,
+        renderer: fn(&mut Renderers) -> &mut Handlebars<'static>,
+    ) -> Result<String, String> {
+        let mut vars = self.config.var_defaults.clone();
+        vars.extend(self.config.vars.clone());
         vars.extend(bookmark.vars.clone());
+        self.resolve_vars(&mut vars)?;
 
-        handlebars
-            .render_template(template, &vars)
+        // Strict mode reports missing variables one at a time, so optional ones
+        // are filled with empty strings until a required one is hit.
+        let mut renderers = self.renderers();
+        let handlebars = renderer(&mut renderers);
+        let rendered = loop {
+            match handlebars.render_template(template, &vars) {
+                Err(e) => match e.reason() {
+                    RenderErrorReason::MissingVariable(Some(name))
+                        if self.config.required_vars.contains(name) =>
+                    {
+                        return Err(format!(
+                            "Required variable '{}' is not set for bookmark '{}'",
+                            name, bookmark.name
+                        ));
+                    }
+                    RenderErrorReason::MissingVariable(Some(name)) if !vars.contains_key(name) => {
+                        vars.insert(name.clone(), String::new());
+                    }
+                    _ => {
+                        handlebars.set_strict_mode(false);
+                        let rendered = handlebars.render_template(template, &vars);
+                        handlebars.set_strict_mode(true);
+                        break rendered;
+                    }
+                },
+                result => break result,
+            }
+        };
+
+        rendered
             .map(|s| s.trim_start().trim_end().to_string())
             .map_err(|e| format!("Template rendering error: {}", e))
     }
 
-    fn gen_command(&self, bookmark: &bookmark::Bookmark) -> Result<String, String> {
-        let mut processed = HashSet::new();
-        let mut cmd = self.gen_template_command(bookmark.clone(), &mut processed)?;
+    // Dry run never executes anything, whatever the exec settings say.
+    pub(crate) fn executes(&self, bookmark: &Bookmark) -> bool {
+        !self.dry_run && bookmark.exec.unwrap_or(self.exec)
+    }
 
-        let exec = bookmark.exec.unwrap_or(self.exec);
+    fn gen_command(&self, bookmark: &bookmark::Bookmark, args: &str) -> Result<String, String> {
+        let mut cmd = self.gen_preview_with_args(bookmark, args)?;
 
-        if exec {
+        if self.executes(bookmark) {
             cmd.push('\n');
         }
 
         Ok(cmd)
     }
 
-    pub(crate) fn update(&mut self, event: Event) -> bool {
-        if let Event::Key(key) = event {
-            self.handle_key_event(key)
-        } else {
-            false
+    // The command as it would be sent, without the exec newline.
+    pub(crate) fn gen_preview(&self, bookmark: &Bookmark) -> Result<String, String> {
+        self.gen_preview_with_args(bookmark, "")
+    }
+
+    // Args are appended to the rendered commands before they are wrapped in a shell.
+    fn gen_preview_with_args(&self, bookmark: &Bookmark, args: &str) -> Result<String, String> {
+        let mut cmd = self.gen_template_command(bookmark.clone(), &mut HashSet::new(), 0)?;
+        if !args.trim().is_empty() {
+            if !cmd.is_empty() {
+                cmd.push(' ');
+            }
+            cmd.push_str(args.trim());
         }
+        let cmd = match bookmark.shell.as_ref().or(self.shell.as_ref()) {
+            Some(shell) => wrap_command(shell, &cmd),
+            None => cmd,
+        };
+        self.with_env(bookmark, cmd)
     }
 
-    fn handle_key_event(&mut self, key: KeyWithModifier) -> bool {
-        let mut should_render = false;
+    // Sets the bookmark's env for the command only, so it doesn't leak into
+    // the shell the command is pasted to.
+    fn with_env(&self, bookmark: &Bookmark, cmd: String) -> Result<String, String> {
+        if bookmark.env.is_empty() {
+            return Ok(cmd);
+        }
+        let cmd = match bookmark.shell.as_ref().or(self.shell.as_ref()) {
+            Some(_) => cmd,
+            None => wrap_command("sh", &cmd),
+        };
+        let mut assignments = Vec::new();
+        for (name, value) in bookmark.env.iter().collect::<BTreeMap<_, _>>() {
+            let value = self.gen_value_with_vars(value, bookmark)?;
+            assignments.push(format!("{}={}", name, shell_quote(&value)));
+        }
+        Ok(format!("env {} {}", assignments.join(" "), cmd))
+    }
 
-        match key.bare_key {
-            // Not configurable keys
-            BareKey::Esc => close_focus(),
-            BareKey::Char('c') if key.has_modifiers(&[KeyModifier::Ctrl]) => {
-                close_focus();
-            }
-            BareKey::Down | BareKey::Tab => match self.mode {
-                Mode::Bookmarks => {
-                    self.bookmarks.select_down();
-                    should_render = true;
-                }
-                Mode::Labels => {
-                    self.labels.select_down();
-                    should_render = true;
+    // One JSON object per line with every bookmark's expanded command, or the
+    // error it fails with, and the number of errors.
+    pub(crate) fn commands_dump(&self) -> (String, usize) {
+        let mut dump = String::new();
+        let mut errors = 0;
+        for bookmark in self.config.bookmarks.iter() {
+            let line = match self.gen_preview(bookmark) {
+                Ok(cmd) => serde_json::json!({ "name": bookmark.name, "command": cmd }),
+                Err(e) => {
+                    errors += 1;
+                    serde_json::json!({ "name": bookmark.name, "error": e })
                 }
-                Mode::Edit => {
-                    self.editable_files.select_down();
-                    should_render = true;
-                }
-                _ => {}
-            },
-            BareKey::Up => match self.mode {
-                Mode::Bookmarks => {
-                    self.bookmarks.select_up();
-                    should_render = true;
-                }
-                Mode::Labels => {
-                    self.labels.select_up();
-                    should_render = true;
-                }
-                Mode::Edit => {
-                    self.editable_files.select_up();
-                    should_render = true;
+            };
+            dump.push_str(&line.to_string());
+            dump.push('\n');
+        }
+        (dump, errors)
+    }
+
+    fn gen_workflow_steps(&self, workflow: &Workflow) -> Result<Vec<WorkflowStep>, String> {
+        let mut steps = Vec::new();
+
+        for name in workflow.steps.iter() {
+            let Some(bookmark) = self.config.bookmarks.iter().find(|b| &b.name == name) else {
+                return Err(format!("Bookmark '{}' not found", name));
+            };
+            let cmd = self.gen_preview(bookmark)?;
+            steps.push(WorkflowStep {
+                bookmark: name.clone(),
+                cmd,
+            });
+        }
+
+        Ok(steps)
+    }
+
+    // References of the bookmark to cmds and bookmarks that don't exist, with
+    // the messages gen_template_command would fail with.
+    fn dangling_references(&self, bookmark: &Bookmark) -> Vec<String> {
+        bookmark
+            .cmds
+            .iter()
+            .filter_map(|cmd| {
+                if let Some(name) = cmd.strip_prefix("bookmark::") {
+                    if !self.config.bookmarks.iter().any(|b| b.name == name) {
+                        return Some(format!("Bookmark '{}' not found", name));
+                    }
+                } else if let Some(key) = cmd.strip_prefix("cmd::") {
+                    if !self.config.cmds.contains_key(key) {
+                        return Some(format!("Command key '{}' not found in cmds", key));
+                    }
                 }
-                _ => {}
-            },
-            BareKey::Right => {
-                self.mode = self.mode.next();
-                self.filter_mode = FilterMode::default();
-                self.set_filter();
-                should_render = true;
+                None
+            })
+            .collect()
+    }
+
+    pub(crate) fn report_diagnostics(&mut self) {
+        let Some(first) = self.diagnostics.first() else {
+            return;
+        };
+        let message = format!(
+            "The config has {} problem(s), see {} mode. First: {}",
+            self.diagnostics.len(),
+            Mode::Diagnostics,
+            first
+        );
+        self.error_mgr.handle_error(message);
+    }
+
+    // Dry-runs every bookmark and workflow and collects all problems, each
+    // prefixed with the offending name. Prompted vars are treated as set.
+    pub(crate) fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for bookmark in self.config.bookmarks.iter() {
+            // Rendering stops at the first dangling reference, so all of them
+            // are listed without rendering.
+            let references = self.dangling_references(bookmark);
+            if !references.is_empty() {
+                problems.extend(
+                    references
+                        .into_iter()
+                        .map(|err| format!("Bookmark '{}': {}", bookmark.name, err)),
+                );
+                continue;
             }
-            BareKey::Left => {
-                self.mode = self.mode.prev();
-                self.filter_mode = FilterMode::default();
-                self.set_filter();
-                should_render = true;
+            let mut bookmark = bookmark.clone();
+            for var in bookmark.prompt_vars.iter() {
+                bookmark.vars.entry(var.clone()).or_default();
             }
-            BareKey::Char(c) if key.has_modifiers(&[KeyModifier::Ctrl]) && c.is_ascii_digit() => {
-                if let Some(digit) = c.to_digit(10) {
-                    if let Ok(mode) = Mode::try_from(digit) {
-                        if self.mode != mode {
-                            self.mode = mode;
-                            self.filter_mode = FilterMode::default();
-                            self.set_filter();
-                            should_render = true;
-                        }
-                    }
-                }
+            let name = bookmark.name.clone();
+            if let Err(err) = self.gen_template_command(bookmark, &mut HashSet::new(), 0) {
+                problems.push(format!("Bookmark '{}': {}", name, err));
             }
-            BareKey::Char(c) if key.has_no_modifiers() => match self.mode {
-                Mode::Bookmarks | Mode::Labels | Mode::Edit => {
-                    if self.detect_filter_mode && self.filter.is_empty() {
-                        if c.is_ascii_digit() {
-                            self.filter_mode = FilterMode::ID
-                        } else if self.filter_mode == FilterMode::ID {
-                            self.filter_mode = FilterMode::Name
-                        }
+        }
+
+        let mut shortcuts: BTreeMap<char, Vec<&str>> = BTreeMap::new();
+        for bookmark in self.config.bookmarks.iter() {
+            if let Some(key) = bookmark.key {
+                shortcuts.entry(key).or_default().push(&bookmark.name);
+            }
+        }
+        for (key, names) in shortcuts.iter().filter(|(_, names)| names.len() > 1) {
+            problems.push(format!(
+                "Shortcut key '{}' is used by several bookmarks: {}",
+                key,
+                names.join(", ")
+            ));
+        }
+
+        let mut workflows: Vec<&String> = self.config.workflows.keys().collect();
+        workflows.sort();
+        for name in workflows {
+            for step in self.config.workflows[name].iter() {
+                if !self.config.bookmarks.iter().any(|b| &b.name == step) {
+                    problems.push(format!(
+                        "Workflow '{}': Bookmark '{}' not found",
+                        name, step
+                    ));
+                }
+            }
+        }
+
+        problems
+    }
+
+    fn run_workflow(&mut self, workflow: &Workflow) -> Result<(), String> {
+        if let Some(run) = self.workflow_run.as_ref() {
+            return Err(format!("Workflow '{}' is still running", run.name));
+        }
+        if self.dry_run {
+            return Err("Workflows can't run in dry run mode".to_string());
+        }
+
+        let steps = self.gen_workflow_steps(workflow)?;
+        self.workflow_run = Some(WorkflowRun {
+            name: workflow.name.clone(),
+            steps: VecDeque::from(steps),
+        });
+        self.run_next_workflow_step();
+
+        Ok(())
+    }
+
+    fn run_next_workflow_step(&mut self) {
+        let Some(run) = self.workflow_run.as_mut() else {
+            return;
+        };
+        let Some(step) = run.steps.pop_front() else {
+            self.workflow_run = None;
+            return;
+        };
+
+        let command = shell_command(step.cmd);
+        let context = BTreeMap::from([
+            (CONTEXT_WORKFLOW.to_string(), run.name.clone()),
+            (CONTEXT_WORKFLOW_STEP.to_string(), step.bookmark),
+        ]);
+        open_command_pane(command, context);
+    }
+
+    fn run_bookmark(
+        &mut self,
+        bookmark: &Bookmark,
+        target: RunTarget,
+        args: &str,
+    ) -> Result<(), String> {
+        // The command is generated while the plugin is still open to show
+        // its errors.
+        let cmd = self.gen_command(bookmark, args)?;
+        // Pasting into the current pane needs the focus back on it.
+        if self.close_after_run || matches!(target, RunTarget::Current) {
+            close_focus();
+        }
+        self.send_bookmark(bookmark, target, cmd);
+        Ok(())
+    }
+
+    // Sends the generated command to the target. For new panes and tabs, exec
+    // runs the command in a command pane, otherwise it is pasted into a new shell.
+    fn send_bookmark(&mut self, bookmark: &Bookmark, target: RunTarget, cmd: String) {
+        let exec = self.executes(bookmark);
+        let cwd = self.get_cwd();
+        let command = self.pane_command(&cmd);
+        // Lets the exit of the command pane be traced back to the bookmark.
+        let context = || BTreeMap::from([(CONTEXT_BOOKMARK.to_string(), bookmark.name.clone())]);
+
+        self.record_history(bookmark);
+
+        match target {
+            RunTarget::Current => write_chars(cmd.as_str()),
+            RunTarget::NewPane if exec => {
+                open_command_pane(command, context());
+            }
+            RunTarget::NewPane => {
+                if let Some(pane_id) = open_terminal(cwd.clone()) {
+                    write_chars_to_pane_id(cmd.as_str(), pane_id);
+                }
+            }
+            RunTarget::Floating if exec => {
+                open_command_pane_floating(command, None, context());
+            }
+            RunTarget::Floating => {
+                if let Some(pane_id) = open_terminal_floating(cwd.clone(), None) {
+                    write_chars_to_pane_id(cmd.as_str(), pane_id);
+                }
+            }
+            RunTarget::NewTab if exec => {
+                open_command_pane_in_new_tab(command, context());
+            }
+            // There is no plain terminal in a new tab to write to, so the tab
+            // gets a command pane running the user's shell.
+            RunTarget::NewTab => {
+                let shell = CommandToRun {
+                    cwd: Some(cwd.clone()),
+                    ..shell_command(INTERACTIVE_SHELL.to_string())
+                };
+                if let (_, Some(pane_id)) = open_command_pane_in_new_tab(shell, BTreeMap::new()) {
+                    write_chars_to_pane_id(cmd.as_str(), pane_id);
+                }
+            }
+        }
+    }
+
+    // The command pane running the generated command. The bookmark's cwd is
+    // already changed to by the command itself.
+    fn pane_command(&self, cmd: &str) -> CommandToRun {
+        CommandToRun {
+            cwd: Some(self.get_cwd()),
+            ..shell_command(cmd.trim_end().to_string())
+        }
+    }
+
+    fn toggle_marked(&mut self, name: &str) {
+        match self.marked.iter().position(|marked| marked == name) {
+            Some(position) => {
+                self.marked.remove(position);
+            }
+            None => self.marked.push(name.to_string()),
+        }
+    }
+
+    // The marked bookmarks are joined in marking order into one command, which
+    // only executes if every one of them would.
+    fn gen_marked_command(&self) -> Result<String, String> {
+        let mut cmds = Vec::new();
+        let mut exec = true;
+        for name in self.marked.iter() {
+            let Some(bookmark) = self.config.bookmarks.iter().find(|b| &b.name == name) else {
+                return Err(format!("Bookmark '{}' not found", name));
+            };
+            if !bookmark.prompt_vars.is_empty() || bookmark.confirm {
+                return Err(format!(
+                    "Bookmark '{}' asks for input and can't run with others",
+                    name
+                ));
+            }
+            cmds.push(self.gen_preview(bookmark)?);
+            exec &= self.executes(bookmark);
+        }
+        let mut cmd = cmds.join(self.separator.as_str());
+        if exec {
+            cmd.push('\n');
+        }
+        Ok(cmd)
+    }
+
+    fn run_marked(&mut self) -> Result<(), String> {
+        let cmd = self.gen_marked_command()?;
+        let bookmarks: Vec<Bookmark> = self
+            .marked
+            .iter()
+            .filter_map(|name| self.config.bookmarks.iter().find(|b| &b.name == name))
+            .cloned()
+            .collect();
+        for bookmark in bookmarks.iter() {
+            self.record_history(bookmark);
+        }
+        self.marked.clear();
+
+        close_focus();
+        write_chars(cmd.as_str());
+
+        Ok(())
+    }
+
+    fn record_history(&mut self, bookmark: &Bookmark) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.history
+            .record(bookmark.name.as_str(), now, self.recent_limit);
+        if let Err(e) = self.write_history() {
+            self.error_mgr
+                .handle_error(format!("Failed to save history: {}.", e));
+        }
+        self.refresh_recent();
+    }
+
+    // Asks for the bookmark's prompt_vars first, if it has any.
+    // Bookmarks marked with confirm wait for y/n first.
+    fn start_bookmark(
+        &mut self,
+        bookmark: Bookmark,
+        target: RunTarget,
+        args: String,
+    ) -> Result<(), String> {
+        if bookmark.confirm {
+            self.confirm_run = Some(PendingRun {
+                bookmark,
+                target,
+                args,
+            });
+            return Ok(());
+        }
+        self.start_confirmed_bookmark(bookmark, target, args)
+    }
+
+    fn start_confirmed_bookmark(
+        &mut self,
+        bookmark: Bookmark,
+        target: RunTarget,
+        args: String,
+    ) -> Result<(), String> {
+        if bookmark.prompt_vars.is_empty() {
+            return self.run_bookmark(&bookmark, target, &args);
+        }
+        let mut prompt = VarPrompt::new(bookmark, target);
+        prompt.args = args;
+        self.prompt = Some(prompt);
+        Ok(())
+    }
+
+    fn handle_append_args_key_event(&mut self, key: KeyWithModifier) -> bool {
+        let Some(args) = self.append_args.as_mut() else {
+            return false;
+        };
+
+        match key.bare_key {
+            BareKey::Esc => self.append_args = None,
+            BareKey::Char('c') if key.has_modifiers(&[KeyModifier::Ctrl]) => close_focus(),
+            BareKey::Char(c) if key.has_no_modifiers() => args.push(c),
+            BareKey::Backspace => {
+                args.pop();
+            }
+            BareKey::Enter => {
+                let args = self.append_args.take().expect("append args are set");
+                if let Some(bookmark) = self.bookmarks.get_selected().cloned() {
+                    let target = bookmark.run_in.unwrap_or_default();
+                    if let Err(err) = self.start_bookmark(bookmark, target, args) {
+                        self.error_mgr
+                            .handle_error(format!("Failed to generate command: {}", err));
                     }
-                    match self.filter_mode {
-                        FilterMode::ID => {
-                            if let Some(digit) = c.to_digit(10) {
-                                if !self.filter.is_empty() || digit > 0 {
-                                    self.filter.push(c);
+                }
+            }
+            _ => return false,
+        }
 
-                                    self.set_filter();
+        true
+    }
 
-                                    should_render = true;
-                                }
-                            }
-                        }
-                        _ => {
-                            self.filter.push(c);
+    fn handle_prompt_key_event(&mut self, key: KeyWithModifier) -> bool {
+        let Some(prompt) = self.prompt.as_mut() else {
+            return false;
+        };
 
-                            self.set_filter();
+        match key.bare_key {
+            BareKey::Esc => self.prompt = None,
+            BareKey::Char('c') if key.has_modifiers(&[KeyModifier::Ctrl]) => close_focus(),
+            BareKey::Char(c) if key.has_no_modifiers() => prompt.input.push(c),
+            BareKey::Backspace => {
+                prompt.input.pop();
+            }
+            BareKey::Enter => {
+                if prompt.submit() {
+                    let prompt = self.prompt.take().expect("prompt is set");
+                    if let Err(err) =
+                        self.run_bookmark(&prompt.bookmark, prompt.target, &prompt.args)
+                    {
+                        self.error_mgr
+                            .handle_error(format!("Failed to generate command: {}", err));
+                    }
+                }
+            }
+            _ => return false,
+        }
 
-                            should_render = true;
-                        }
+        true
+    }
+
+    fn duplicate_selected(&mut self, bookmark: &Bookmark) {
+        let copy = match self.duplicate_bookmark(bookmark) {
+            Ok(copy) => copy,
+            Err(e) => {
+                self.error_mgr.handle_error(format!(
+                    "Failed to duplicate bookmark '{}': {}.",
+                    bookmark.name, e
+                ));
+                return;
+            }
+        };
+        if let Err(e) = self.load_config() {
+            self.error_mgr.handle_error(format!(
+                "Failed to load config file '{}': {}.",
+                self.get_path().display(),
+                e
+            ));
+            return;
+        }
+        self.set_filter();
+        self.bookmarks.select_named(&copy.name);
+
+        if self.edit_duplicate {
+            if let Some(copy) = self.bookmarks.get_selected().cloned() {
+                self.open_bookmark_file(&copy);
+            }
+        }
+    }
+
+    // Opens the file the bookmark was loaded from at the line of its name.
+    fn open_bookmark_file(&mut self, bookmark: &Bookmark) {
+        match self.bookmark_location(bookmark) {
+            Ok((path, line)) => {
+                let mut file = FileToOpen::new(path).with_cwd(self.get_cwd());
+                if let Some(line) = line {
+                    file = file.with_line_number(line);
+                }
+                open_file_in_place(file, Default::default());
+            }
+            Err(e) => self.error_mgr.handle_error(format!(
+                "Failed to open bookmark '{}': {}.",
+                bookmark.name, e
+            )),
+        }
+    }
+
+    fn handle_form_key_event(&mut self, key: KeyWithModifier) -> bool {
+        let Some(form) = self.form.as_mut() else {
+            return false;
+        };
+
+        match key.bare_key {
+            BareKey::Esc => self.form = None,
+            BareKey::Char('c') if key.has_modifiers(&[KeyModifier::Ctrl]) => close_focus(),
+            BareKey::Char(c) if key.has_no_modifiers() => form.input.push(c),
+            BareKey::Backspace => {
+                form.input.pop();
+            }
+            BareKey::Enter => {
+                let names: HashSet<String> = self
+                    .config
+                    .bookmarks
+                    .iter()
+                    .map(|b| b.name.clone())
+                    .collect();
+                if form.submit(&names) {
+                    let bookmark = form.to_bookmark();
+                    self.form = None;
+                    if let Err(e) = self.append_bookmark(bookmark) {
+                        self.error_mgr.handle_error(format!(
+                            "Failed to save bookmark to '{}': {}.",
+                            self.get_path().display(),
+                            e
+                        ));
+                    } else if let Err(e) = self.load_config() {
+                        self.error_mgr.handle_error(format!(
+                            "Failed to load config file '{}': {}.",
+                            self.get_path().display(),
+                            e
+                        ));
                     }
                 }
-                _ => {}
-            },
-            BareKey::Backspace => match self.mode {
-                Mode::Bookmarks | Mode::Labels | Mode::Edit => {
-                    self.filter.pop();
+            }
+            _ => return false,
+        }
 
-                    self.set_filter();
+        true
+    }
 
-                    should_render = true;
+    fn handle_confirm_run_key_event(&mut self, key: KeyWithModifier) -> bool {
+        let Some(run) = self.confirm_run.take() else {
+            return false;
+        };
+
+        match key.bare_key {
+            BareKey::Char('y') | BareKey::Char('Y') if key.has_no_modifiers() => {
+                if let Err(err) = self.start_confirmed_bookmark(run.bookmark, run.target, run.args)
+                {
+                    self.error_mgr
+                        .handle_error(format!("Failed to generate command: {}", err));
                 }
-                _ => {}
+            }
+            BareKey::Char('c') if key.has_modifiers(&[KeyModifier::Ctrl]) => close_focus(),
+            BareKey::Esc | BareKey::Char('n') | BareKey::Char('N') => {}
+            // Keep asking until the run is confirmed or cancelled.
+            _ => self.confirm_run = Some(run),
+        }
+
+        true
+    }
+
+    // Any key ends the wait. Keys without a bookmark are reported, except
+    // for Esc, which just cancels.
+    fn handle_shortcut_key_event(&mut self, key: KeyWithModifier) -> bool {
+        self.shortcut_pending = false;
+
+        match key.bare_key {
+            BareKey::Char(c) if key.has_no_modifiers() => match self.shortcut_bookmark(c) {
+                Some(bookmark) => {
+                    let target = bookmark.run_in.unwrap_or_default();
+                    if let Err(err) = self.start_bookmark(bookmark, target, String::new()) {
+                        self.error_mgr
+                            .handle_error(format!("Failed to generate command: {}", err));
+                    }
+                }
+                None => self
+                    .error_mgr
+                    .handle_error(format!("No bookmark has the shortcut key '{}'", c)),
             },
-            BareKey::Enter => match self.mode {
-                Mode::Bookmarks => {
-                    match self.bookmarks.get_selected() {
-                        Some(bookmark) => match self.gen_command(bookmark) {
-                            Ok(cmd) => {
-                                close_focus();
-                                write_chars(cmd.as_str());
-                            }
-                            Err(err) => {
-                                self.error_mgr
-                                    .handle_error(format!("Failed to generate command: {}", err));
-                                should_render = true;
-                            }
-                        },
-                        None => should_render = true,
-                    };
+            BareKey::Char('c') if key.has_modifiers(&[KeyModifier::Ctrl]) => close_focus(),
+            _ => {}
+        }
+
+        true
+    }
+
+    fn handle_confirm_delete_key_event(&mut self, key: KeyWithModifier) -> bool {
+        let Some(bookmark) = self.confirm_delete.take() else {
+            return false;
+        };
+
+        match key.bare_key {
+            BareKey::Char('y') | BareKey::Char('Y') if key.has_no_modifiers() => {
+                let position = self.bookmarks.get_position();
+                if let Err(e) = self.remove_bookmark(&bookmark) {
+                    self.error_mgr.handle_error(format!(
+                        "Failed to delete bookmark '{}': {}.",
+                        bookmark.name, e
+                    ));
+                } else if let Err(e) = self.load_config() {
+                    self.error_mgr.handle_error(format!(
+                        "Failed to load config file '{}': {}.",
+                        self.get_path().display(),
+                        e
+                    ));
                 }
-                Mode::Labels => {
-                    self.filter_mode = FilterMode::Label;
-                    self.filter = match self.labels.get_selected() {
-                        Some(label) => label.name.clone(),
-                        None => String::new(),
-                    };
-                    self.mode = Mode::Bookmarks;
-                    self.view_desc = false;
+                self.set_filter();
+                self.bookmarks.select(position);
+            }
+            BareKey::Char('c') if key.has_modifiers(&[KeyModifier::Ctrl]) => close_focus(),
+            BareKey::Esc | BareKey::Char('n') | BareKey::Char('N') => {}
+            // Keep asking until the deletion is confirmed or cancelled.
+            _ => self.confirm_delete = Some(bookmark),
+        }
+
+        true
+    }
+
+    fn handle_command_pane_exited(
+        &mut self,
+        exit_code: Option<i32>,
+        context: BTreeMap<String, String>,
+    ) -> bool {
+        let recorded = self.record_exit(exit_code, &context);
+        let Some(run) = self.workflow_run.as_ref() else {
+            return recorded;
+        };
+        if context.get(CONTEXT_WORKFLOW) != Some(&run.name) {
+            return recorded;
+        }
+
+        if exit_code == Some(0) {
+            self.run_next_workflow_step();
+            return recorded;
+        }
+
+        let step = context
+            .get(CONTEXT_WORKFLOW_STEP)
+            .cloned()
+            .unwrap_or_default();
+        let exit_code = exit_code.map_or("unknown".to_string(), |code| code.to_string());
+        self.error_mgr.handle_error(format!(
+            "Workflow '{}' stopped at step '{}': exit code {}",
+            run.name, step, exit_code
+        ));
+        self.workflow_run = None;
+
+        true
+    }
+
+    // Bookmarks run on their own and as workflow steps both report their exit.
+    fn record_exit(&mut self, exit_code: Option<i32>, context: &BTreeMap<String, String>) -> bool {
+        let Some(name) = context
+            .get(CONTEXT_BOOKMARK)
+            .or_else(|| context.get(CONTEXT_WORKFLOW_STEP))
+        else {
+            return false;
+        };
+        let Some(exit_code) = exit_code else {
+            return false;
+        };
+        if !self.history.record_exit(name, exit_code) {
+            return false;
+        }
+        if let Err(e) = self.write_history() {
+            self.error_mgr
+                .handle_error(format!("Failed to save history: {}.", e));
+        }
+        true
+    }
+
+    // On failure the last good config stays loaded, along with the filter and
+    // selection, so the file can be fixed from Edit mode.
+    fn reload(&mut self) {
+        if !self.reload_config() {
+            return;
+        }
+
+        self.filter = "".to_string();
+
+        self.reset_selection();
+
+        self.mode = Mode::default();
+    }
+
+    // Reloads without leaving the current view: the mode and filter are kept
+    // and the selected item stays selected if it is still listed.
+    fn reload_soft(&mut self) {
+        let name = self.selected_name();
+        let position = self.position();
+        if !self.reload_config() {
+            return;
+        }
+
+        self.set_filter();
+        match name {
+            Some(name) => self.select_named(&name, position),
+            None => self.select(position),
+        }
+    }
+
+    fn reload_config(&mut self) -> bool {
+        if let Err(e) = self.load_config() {
+            self.error_mgr.handle_error(format!(
+                "Failed to load config file '{}': {}. The last loaded config is kept.",
+                self.get_path().display(),
+                e
+            ));
+            return false;
+        }
+        self.report_diagnostics();
+
+        if !self.modified_bookmarks.is_empty() {
+            set_timeout(MODIFIED_MARKER_TIMEOUT);
+        }
+        true
+    }
+
+    fn handle_file_system_event(&mut self, paths: Vec<(PathBuf, Option<FileMetadata>)>) -> bool {
+        if !paths.iter().any(|(path, _)| self.config_changed(path)) {
+            return false;
+        }
+
+        if self.auto_reload {
+            self.reload();
+            self.notice = Some("Config reloaded after an external change".to_string());
+            set_timeout(MODIFIED_MARKER_TIMEOUT);
+        } else {
+            self.notice = Some(format!(
+                "Config changed, press {} to reload",
+                self.keybindings.reload
+            ));
+        }
+
+        true
+    }
+
+    // Zellij doesn't always re-render a plugin whose pane was resized, so the
+    // layout is redrawn as soon as the manifest reports a new size.
+    fn handle_pane_update(&mut self, manifest: PaneManifest) -> bool {
+        let Some(plugin_id) = self.plugin_id else {
+            return false;
+        };
+        match plugin_pane_size(&manifest, plugin_id) {
+            Some(size) => size != self.pane_size,
+            None => false,
+        }
+    }
+
+    pub(crate) fn pipe(&mut self, pipe_message: PipeMessage) -> bool {
+        let message = pipe_message
+            .payload
+            .as_deref()
+            .unwrap_or(pipe_message.name.as_str());
+        let result = match message.parse::<PipeCommand>() {
+            Ok(PipeCommand::Run(name)) => self.run_piped_bookmark(&name),
+            Ok(PipeCommand::Filter(filter)) => {
+                self.set_piped_filter(filter);
+                Ok(())
+            }
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
+            // Pipes from the CLI get the error back on their stdout.
+            if let PipeSource::Cli(_) = pipe_message.source {
+                cli_pipe_output(&pipe_message.name, &format!("{}\n", err));
+            }
+            self.error_mgr
+                .handle_error(format!("Failed to handle pipe message: {}.", err));
+        }
+        true
+    }
+
+    // The text is a name filter, whatever was being filtered by before.
+    fn set_piped_filter(&mut self, filter: String) {
+        if self.mode != Mode::Bookmarks {
+            self.switch_mode(Mode::Bookmarks);
+        }
+        self.filter_mode = FilterMode::Name;
+        self.filter = filter;
+        self.set_filter();
+        self.reset_selection();
+    }
+
+    // A pipe usually arrives while another pane has the focus, so the plugin
+    // is left as it is and nothing is typed into the focused pane: the current
+    // pane is swapped for a new one. There is no one to answer a prompt either.
+    fn piped_bookmark(&self, name: &str) -> Result<(Bookmark, RunTarget), String> {
+        let bookmark = self
+            .config
+            .bookmarks
+            .iter()
+            .find(|b| b.name == name)
+            .cloned()
+            .ok_or_else(|| format!("bookmark '{}' not found", name))?;
+        if bookmark.confirm || !bookmark.prompt_vars.is_empty() {
+            return Err(format!(
+                "bookmark '{}' asks for input and can't run from a pipe",
+                name
+            ));
+        }
+        let target = match bookmark.run_in.unwrap_or_default() {
+            RunTarget::Current => RunTarget::NewPane,
+            target => target,
+        };
+        Ok((bookmark, target))
+    }
+
+    fn run_piped_bookmark(&mut self, name: &str) -> Result<(), String> {
+        let (bookmark, target) = self.piped_bookmark(name)?;
+        let cmd = self.gen_command(&bookmark, "")?;
+        self.send_bookmark(&bookmark, target, cmd);
+        Ok(())
+    }
+
+    pub(crate) fn update(&mut self, event: Event) -> bool {
+        match event {
+            Event::Key(key) => self.handle_key_event(key),
+            Event::CommandPaneExited(_, exit_code, context) => {
+                self.handle_command_pane_exited(exit_code, context)
+            }
+            Event::PermissionRequestResult(PermissionStatus::Granted) => {
+                // Plugins don't inherit the host environment, so it is captured from a shell.
+                let context =
+                    BTreeMap::from([(CONTEXT_SOURCE.to_string(), CONTEXT_SOURCE_ENV.to_string())]);
+                run_command(&["env", "-0"], context);
+                false
+            }
+            Event::RunCommandResult(Some(0), stdout, _, context)
+                if context.get(CONTEXT_SOURCE).map(String::as_str) == Some(CONTEXT_SOURCE_ENV) =>
+            {
+                self.env = parse_env(&stdout);
+                // The env helper owns a copy of the env, so the renderers are rebuilt.
+                self.renderers.replace(None);
+                self.template_cache.borrow_mut().clear();
+                false
+            }
+            Event::FileSystemCreate(paths)
+            | Event::FileSystemUpdate(paths)
+            | Event::FileSystemDelete(paths) => self.handle_file_system_event(paths),
+            Event::PaneUpdate(manifest) => self.handle_pane_update(manifest),
+            Event::ModeUpdate(mode_info) => {
+                let theme = ThemeColors::from_styling(&mode_info.style.colors);
+                let changed = theme != self.ui_style.theme;
+                self.ui_style.theme = theme;
+                changed
+            }
+            Event::BeforeClose => {
+                self.save_session();
+                false
+            }
+            Event::Timer(_) => {
+                let should_render = !self.modified_bookmarks.is_empty() || self.notice.is_some();
+                self.modified_bookmarks.clear();
+                self.notice = None;
+                should_render
+            }
+            _ => false,
+        }
+    }
+
+    fn handle_key_event(&mut self, key: KeyWithModifier) -> bool {
+        if self.prompt.is_some() {
+            return self.handle_prompt_key_event(key);
+        }
+        if self.form.is_some() {
+            return self.handle_form_key_event(key);
+        }
+        if self.confirm_delete.is_some() {
+            return self.handle_confirm_delete_key_event(key);
+        }
+        if self.confirm_run.is_some() {
+            return self.handle_confirm_run_key_event(key);
+        }
+        if self.append_args.is_some() {
+            return self.handle_append_args_key_event(key);
+        }
+        if self.shortcut_pending {
+            return self.handle_shortcut_key_event(key);
+        }
+
+        let mut should_render = false;
+
+        match key.bare_key {
+            // Not configurable keys
+            _ if self.keybindings.exit.matches(&key) => close_focus(),
+            BareKey::Char('c') if key.has_modifiers(&[KeyModifier::Ctrl]) => {
+                close_focus();
+            }
+            BareKey::Tab if key.has_no_modifiers() && self.label_completion().is_some() => {
+                if let Some(filter) = self.label_completion() {
+                    self.filter = filter;
+                    self.set_filter();
+                }
+                should_render = true;
+            }
+            BareKey::Tab if !self.label_suggestions().is_empty() => {
+                self.select_suggestion(key.has_modifiers(&[KeyModifier::Shift]));
+                should_render = true;
+            }
+            _ if self.keybindings.down.matches(&key)
+                || key == KeyWithModifier::new(BareKey::Tab) =>
+            {
+                match self.mode {
+                    Mode::Bookmarks | Mode::Describe => {
+                        self.bookmarks.select_down();
+                        should_render = true;
+                    }
+                    Mode::Labels => {
+                        self.labels.select_down();
+                        should_render = true;
+                    }
+                    Mode::Edit => {
+                        self.editable_files.select_down();
+                        should_render = true;
+                    }
+                    Mode::Workflows => {
+                        self.workflows.select_down();
+                        should_render = true;
+                    }
+                    Mode::Recent => {
+                        self.recent.select_down();
+                        should_render = true;
+                    }
+                    _ => {}
+                }
+            }
+            _ if self.keybindings.up.matches(&key) => match self.mode {
+                Mode::Bookmarks | Mode::Describe => {
+                    self.bookmarks.select_up();
+                    should_render = true;
+                }
+                Mode::Labels => {
+                    self.labels.select_up();
+                    should_render = true;
+                }
+                Mode::Edit => {
+                    self.editable_files.select_up();
+                    should_render = true;
+                }
+                Mode::Workflows => {
+                    self.workflows.select_up();
+                    should_render = true;
+                }
+                Mode::Recent => {
+                    self.recent.select_up();
+                    should_render = true;
+                }
+                _ => {}
+            },
+            BareKey::PageDown => match self.mode {
+                Mode::Bookmarks | Mode::Describe => {
+                    self.bookmarks.select_page_down(self.page_size);
+                    should_render = true;
+                }
+                Mode::Labels => {
+                    self.labels.select_page_down(self.page_size);
+                    should_render = true;
+                }
+                Mode::Edit => {
+                    self.editable_files.select_page_down(self.page_size);
+                    should_render = true;
+                }
+                Mode::Workflows => {
+                    self.workflows.select_page_down(self.page_size);
+                    should_render = true;
+                }
+                Mode::Recent => {
+                    self.recent.select_page_down(self.page_size);
+                    should_render = true;
+                }
+                _ => {}
+            },
+            BareKey::PageUp => match self.mode {
+                Mode::Bookmarks | Mode::Describe => {
+                    self.bookmarks.select_page_up(self.page_size);
+                    should_render = true;
+                }
+                Mode::Labels => {
+                    self.labels.select_page_up(self.page_size);
+                    should_render = true;
+                }
+                Mode::Edit => {
+                    self.editable_files.select_page_up(self.page_size);
+                    should_render = true;
+                }
+                Mode::Workflows => {
+                    self.workflows.select_page_up(self.page_size);
+                    should_render = true;
+                }
+                Mode::Recent => {
+                    self.recent.select_page_up(self.page_size);
+                    should_render = true;
+                }
+                _ => {}
+            },
+            _ if self.keybindings.next_mode.matches(&key) => {
+                self.switch_mode(self.mode.next());
+                should_render = true;
+            }
+            _ if self.keybindings.prev_mode.matches(&key) => {
+                self.switch_mode(self.mode.prev());
+                should_render = true;
+            }
+            BareKey::Char(c) if key.has_modifiers(&[KeyModifier::Ctrl]) && c.is_ascii_digit() => {
+                if let Some(digit) = c.to_digit(10) {
+                    if let Ok(mode) = Mode::try_from(digit) {
+                        if self.mode != mode {
+                            self.switch_mode(mode);
+                            should_render = true;
+                        }
+                    }
+                }
+            }
+            BareKey::Char(c) if key.has_no_modifiers() && self.vim_key(c).is_some() => {
+                if let Some(key) = self.vim_key(c) {
+                    should_render = self.handle_key_event(key);
+                }
+            }
+            _ if self.keybindings.shortcut.matches(&key) => {
+                if self.mode == Mode::Bookmarks {
+                    self.shortcut_pending = true;
+                    should_render = true;
+                }
+            }
+            BareKey::Char('/')
+                if key.has_no_modifiers()
+                    && self.vim_keys
+                    && !self.vim_search
+                    && self.filter.is_empty() =>
+            {
+                self.vim_search = true;
+                should_render = true;
+            }
+            BareKey::Char(c) if key.has_no_modifiers() => match self.mode {
+                Mode::Bookmarks
+                | Mode::Labels
+                | Mode::Edit
+                | Mode::Describe
+                | Mode::Workflows
+                | Mode::Recent => {
+                    if self.detect_filter_mode && self.filter.is_empty() {
+                        if c.is_ascii_digit() {
+                            self.filter_mode = FilterMode::ID
+                        } else if self.filter_mode == FilterMode::ID {
+                            self.filter_mode = FilterMode::Name
+                        }
+                    }
+                    match self.filter_mode {
+                        FilterMode::ID => {
+                            if self.accepts_id_char(c) {
+                                self.filter.push(c);
+
+                                self.filter_changed();
+
+                                should_render = true;
+                            }
+                        }
+                        _ => {
+                            self.filter.push(c);
+
+                            self.filter_changed();
+
+                            should_render = true;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            BareKey::Backspace => match self.mode {
+                Mode::Bookmarks
+                | Mode::Labels
+                | Mode::Edit
+                | Mode::Describe
+                | Mode::Workflows
+                | Mode::Recent => {
+                    // Clearing the filter ends a vim search, then goes back.
+                    if !self.filter.is_empty() || self.vim_search || !self.go_back() {
+                        if self.filter.is_empty() {
+                            self.vim_search = false;
+                        }
+                        self.filter.pop();
+
+                        self.set_filter();
+                    }
+
+                    should_render = true;
+                }
+                _ => {}
+            },
+            _ if self.keybindings.run.matches(&key) && self.suggestion.is_some() => {
+                self.fill_suggestion();
+                should_render = true;
+            }
+            _ if self.keybindings.run.matches(&key) => match self.mode {
+                Mode::Bookmarks if !self.marked.is_empty() => {
+                    if let Err(err) = self.run_marked() {
+                        self.error_mgr
+                            .handle_error(format!("Failed to generate command: {}", err));
+                    }
+                    should_render = true;
+                }
+                Mode::Bookmarks => {
+                    if let Some(bookmark) = self.bookmarks.get_selected().cloned() {
+                        let target = bookmark.run_in.unwrap_or_default();
+                        if let Err(err) = self.start_bookmark(bookmark, target, String::new()) {
+                            self.error_mgr
+                                .handle_error(format!("Failed to generate command: {}", err));
+                        }
+                    }
+                    should_render = true;
+                }
+                Mode::Labels => {
+                    self.push_view();
+                    self.filter_mode = FilterMode::Label;
+                    self.filter = match self.labels.get_selected() {
+                        Some(label) => label.name.clone(),
+                        None => String::new(),
+                    };
+                    self.mode = Mode::Bookmarks;
+                    self.view_desc = false;
+                    self.view_cmds = false;
+                    self.show_details = false;
+
+                    self.set_filter();
+
+                    should_render = true;
+                }
+                Mode::Edit => match self.editable_files.get_selected() {
+                    Some(file) => {
+                        let file = FileToOpen::new(file.path.as_str()).with_cwd(self.get_cwd());
+                        open_file_in_place(file, Default::default());
+                    }
+                    None => should_render = true,
+                },
+                Mode::Recent => {
+                    if let Some(bookmark) = self.recent.get_selected().cloned() {
+                        let target = bookmark.run_in.unwrap_or_default();
+                        if let Err(err) = self.start_bookmark(bookmark, target, String::new()) {
+                            self.error_mgr
+                                .handle_error(format!("Failed to generate command: {}", err));
+                        }
+                    }
+                    should_render = true;
+                }
+                Mode::Workflows => {
+                    if let Some(workflow) = self.workflows.get_selected().cloned() {
+                        match self.run_workflow(&workflow) {
+                            Ok(()) => hide_self(),
+                            Err(err) => {
+                                self.error_mgr
+                                    .handle_error(format!("Failed to run workflow: {}", err));
+                            }
+                        }
+                    }
+                    should_render = true;
+                }
+                _ => {}
+            },
+            _ => {
+                // Configurable keys
+                if self.keybindings.edit.matches(&key) {
+                    self.mode = Mode::Edit;
+                    self.filter = String::new();
+                    self.filter_mode = FilterMode::Name;
+                    self.view_desc = false;
+                    self.view_cmds = false;
+                    self.show_details = false;
+                    self.reset_selection();
+                    self.set_filter();
+                    if let Some(last) = self.override_filenames.last() {
+                        let position = self
+                            .editable_files
+                            .iter()
+                            .position(|(_, item)| &item.value.path == last);
+                        if let Some(position) = position {
+                            self.editable_files.select(position);
+                        }
+                    }
+                    should_render = true;
+                } else if self.keybindings.reload.matches(&key) {
+                    self.notice = None;
+                    self.reload();
+                    should_render = true;
+                } else if self.keybindings.reload_soft.matches(&key) {
+                    self.notice = None;
+                    self.reload_soft();
+                    should_render = true;
+                } else if self.keybindings.switch_filter_label.matches(&key) {
+                    if matches!(self.mode, Mode::Bookmarks | Mode::Describe | Mode::Recent) {
+                        self.filter_mode = self.filter_mode.switch_to(FilterMode::Label);
+                        self.set_filter();
+                        should_render = true;
+                    }
+                } else if self.keybindings.switch_filter_id.matches(&key) {
+                    match self.mode {
+                        Mode::Bookmarks
+                        | Mode::Labels
+                        | Mode::Edit
+                        | Mode::Describe
+                        | Mode::Workflows
+                        | Mode::Recent => {
+                            self.filter_mode = self.filter_mode.switch_to(FilterMode::ID);
+                            self.set_filter();
+                            should_render = true;
+                        }
+                        _ => {}
+                    }
+                } else if self.keybindings.switch_filter_all.matches(&key) {
+                    if matches!(self.mode, Mode::Bookmarks | Mode::Describe | Mode::Recent) {
+                        self.filter_mode = self.filter_mode.switch_to(FilterMode::All);
+                        self.set_filter();
+                        should_render = true;
+                    }
+                } else if self.keybindings.switch_filter_fuzzy.matches(&key) {
+                    if self.mode != Mode::Usage {
+                        self.fuzzy_search =
+                            self.filter_mode != FilterMode::Name || !self.fuzzy_search;
+                        self.filter_mode = FilterMode::Name;
+                        self.set_filter();
+                        should_render = true;
+                    }
+                } else if self.keybindings.toggle_case.matches(&key) {
+                    if self.mode != Mode::Usage {
+                        self.ignore_case = !self.ignore_case;
+                        self.set_filter();
+                        should_render = true;
+                    }
+                } else if self.keybindings.switch_filter_regex.matches(&key) {
+                    if matches!(self.mode, Mode::Bookmarks | Mode::Describe) {
+                        self.filter_mode = self.filter_mode.switch_to(FilterMode::Regex);
+                        self.set_filter();
+                        should_render = true;
+                    }
+                } else if self.keybindings.run_in_pane.matches(&key) {
+                    if self.mode == Mode::Bookmarks {
+                        if let Some(bookmark) = self.bookmarks.get_selected().cloned() {
+                            if let Err(err) =
+                                self.start_bookmark(bookmark, RunTarget::NewPane, String::new())
+                            {
+                                self.error_mgr
+                                    .handle_error(format!("Failed to generate command: {}", err));
+                            }
+                        }
+                        should_render = true;
+                    }
+                } else if self.keybindings.create.matches(&key) {
+                    self.form = Some(BookmarkForm::new());
+                    should_render = true;
+                } else if self.keybindings.delete.matches(&key) {
+                    if self.mode == Mode::Bookmarks {
+                        self.confirm_delete = self.bookmarks.get_selected().cloned();
+                        should_render = true;
+                    }
+                } else if self.keybindings.validate.matches(&key) {
+                    self.diagnostics = self.validate();
+                    self.mode = Mode::Diagnostics;
+                    should_render = true;
+                } else if self.keybindings.pin.matches(&key) {
+                    if self.mode == Mode::Bookmarks {
+                        if let Some(bookmark) = self.bookmarks.get_selected().cloned() {
+                            if let Err(e) = self.toggle_pinned(&bookmark) {
+                                self.error_mgr.handle_error(format!(
+                                    "Failed to pin bookmark '{}': {}.",
+                                    bookmark.name, e
+                                ));
+                            } else if let Err(e) = self.load_config() {
+                                self.error_mgr.handle_error(format!(
+                                    "Failed to load config file '{}': {}.",
+                                    self.get_path().display(),
+                                    e
+                                ));
+                            }
+                            self.set_filter();
+                            self.bookmarks.select_named(&bookmark.name);
+                        }
+                        should_render = true;
+                    }
+                } else if self.keybindings.clear.matches(&key) {
+                    if matches!(self.mode, Mode::Bookmarks | Mode::Labels) {
+                        self.clear_filter();
+                        should_render = true;
+                    }
+                } else if self.keybindings.duplicate.matches(&key) {
+                    if self.mode == Mode::Bookmarks {
+                        if let Some(bookmark) = self.bookmarks.get_selected().cloned() {
+                            self.duplicate_selected(&bookmark);
+                        }
+                        should_render = true;
+                    }
+                } else if self.keybindings.mark.matches(&key) {
+                    if self.mode == Mode::Bookmarks {
+                        if let Some(bookmark) = self.bookmarks.get_selected().cloned() {
+                            self.toggle_marked(&bookmark.name);
+                        }
+                        should_render = true;
+                    }
+                } else if self.keybindings.sort.matches(&key) {
+                    if matches!(self.mode, Mode::Bookmarks | Mode::Labels) {
+                        self.list_order = self.list_order.next(self.mode == Mode::Bookmarks);
+                        self.apply_list_order();
+                        should_render = true;
+                    }
+                } else if self.keybindings.copy.matches(&key) {
+                    if self.mode == Mode::Bookmarks {
+                        if let Some(bookmark) = self.bookmarks.get_selected().cloned() {
+                            match self.gen_preview(&bookmark) {
+                                Ok(cmd) => {
+                                    copy_to_clipboard(cmd);
+                                    self.notice = Some(format!(
+                                        "Copied '{}' to the clipboard",
+                                        bookmark.name
+                                    ));
+                                    set_timeout(MODIFIED_MARKER_TIMEOUT);
+                                }
+                                Err(err) => self
+                                    .error_mgr
+                                    .handle_error(format!("Failed to generate command: {}", err)),
+                            }
+                        }
+                        should_render = true;
+                    }
+                } else if self.keybindings.export.matches(&key) {
+                    if self.mode == Mode::Bookmarks {
+                        // Bookmarks past max_results match the filter too.
+                        let names: Vec<String> = self
+                            .bookmarks
+                            .iter_all()
+                            .map(|bookmark| bookmark.name.clone())
+                            .collect();
+                        match self.export_bookmarks(&names) {
+                            Ok(path) => {
+                                self.notice = Some(format!(
+                                    "Exported {} bookmark(s) to '{}'",
+                                    names.len(),
+                                    path.display()
+                                ));
+                                set_timeout(MODIFIED_MARKER_TIMEOUT);
+                            }
+                            Err(e) => self
+                                .error_mgr
+                                .handle_error(format!("Failed to export bookmarks: {}.", e)),
+                        }
+                        should_render = true;
+                    }
+                } else if self.keybindings.dump_commands.matches(&key) {
+                    match self.dump_commands() {
+                        Ok((path, errors)) => {
+                            let mut notice = format!(
+                                "Wrote {} command(s) to '{}'",
+                                self.config.bookmarks.len(),
+                                path.display()
+                            );
+                            if errors > 0 {
+                                notice.push_str(&format!(", {} failed", errors));
+                            }
+                            self.notice = Some(notice);
+                            set_timeout(MODIFIED_MARKER_TIMEOUT);
+                        }
+                        Err(e) => self
+                            .error_mgr
+                            .handle_error(format!("Failed to write commands: {}.", e)),
+                    }
+                    should_render = true;
+                } else if self.keybindings.import.matches(&key) {
+                    match self.import_bookmarks() {
+                        Ok((added, skipped)) => {
+                            if let Err(e) = self.load_config() {
+                                self.error_mgr.handle_error(format!(
+                                    "Failed to load config file '{}': {}.",
+                                    self.get_path().display(),
+                                    e
+                                ));
+                            } else {
+                                let mut notice = format!("Imported {} bookmark(s)", added);
+                                if !skipped.is_empty() {
+                                    notice.push_str(&format!(
+                                        ", skipped duplicates: {}",
+                                        skipped.join(", ")
+                                    ));
+                                }
+                                self.notice = Some(notice);
+                                self.mode = Mode::Bookmarks;
+                                set_timeout(MODIFIED_MARKER_TIMEOUT);
+                            }
+                        }
+                        Err(e) => self
+                            .error_mgr
+                            .handle_error(format!("Failed to import bookmarks: {}.", e)),
+                    }
+                    should_render = true;
+                } else if self.keybindings.append_args.matches(&key) {
+                    if self.mode == Mode::Bookmarks && self.bookmarks.get_selected().is_some() {
+                        self.append_args = Some(String::new());
+                        should_render = true;
+                    }
+                } else if self.keybindings.edit_bookmark.matches(&key) {
+                    if self.mode == Mode::Bookmarks {
+                        if let Some(bookmark) = self.bookmarks.get_selected().cloned() {
+                            self.open_bookmark_file(&bookmark);
+                        }
+                        should_render = true;
+                    }
+                } else if self.keybindings.preview.matches(&key) {
+                    if self.mode == Mode::Bookmarks {
+                        self.preview = !self.preview;
+                        should_render = true;
+                    }
+                } else if self.keybindings.view_cmds.matches(&key) {
+                    if self.mode == Mode::Bookmarks {
+                        self.view_cmds = !self.view_cmds;
+                        self.view_desc = false;
+                        should_render = true;
+                    }
+                } else if self.keybindings.describe.matches(&key) {
+                    #[allow(clippy::collapsible_if)]
+                    if self.mode == Mode::Bookmarks {
+                        if self.describe_popup {
+                            self.show_details = !self.show_details;
+                        } else {
+                            self.view_desc = !self.view_desc;
+                            self.view_cmds = false;
+                        }
+                        should_render = true;
+                    }
+                }
+            }
+        }
+
+        should_render
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plugin_pane_size, State};
+    use crate::bookmark::{Bookmark, RunTarget};
+    use crate::config::Config;
+    use crate::core::{FilterMode, FilteredList};
+    use crate::label::Label;
+    use crate::session::Session;
+    use crate::workflow::{Workflow, WorkflowStep};
+    use crate::Mode;
+    use std::collections::HashMap;
+    use zellij_tile::prelude::{BareKey, KeyWithModifier, PaneInfo, PaneManifest};
+
+    fn bookmark(name: &str, cmds: &[&str]) -> Bookmark {
+        Bookmark {
+            name: name.to_string(),
+            cmds: cmds.iter().map(|cmd| cmd.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn state_with_config(config: Config) -> State {
+        State {
+            config,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn gen_command_expands_nested_bookmarks() {
+        let dependency = bookmark("dep", &["echo dep-1", "echo dep-2"]);
+        let root = bookmark("root", &["echo start", "bookmark::dep", "echo end"]);
+        let state = state_with_config(Config {
+            bookmarks: vec![dependency, root.clone()],
+            ..Default::default()
+        });
+
+        let cmd = state.gen_command(&root, "").unwrap();
+
+        assert_eq!(cmd, "echo start && echo dep-1 && echo dep-2 && echo end");
+    }
+
+    #[test]
+    fn gen_command_stays_on_one_line_by_default() {
+        let dep = bookmark("dep", &["echo dep", "cmd::done"]);
+        let mut root = bookmark("root", &["echo start", "bookmark::dep"]);
+        root.exec = Some(true);
+        let state = state_with_config(Config {
+            bookmarks: vec![dep, root.clone()],
+            cmds: HashMap::from([("done".to_string(), "echo done".to_string())]),
+            ..Default::default()
+        });
+
+        let cmd = state.gen_command(&root, "--verbose").unwrap();
+        assert!(!cmd.contains("\\\n"));
+        assert_eq!(cmd.trim_end().lines().count(), 1);
+    }
+
+    #[test]
+    fn gen_command_leaves_no_dangling_separators() {
+        let empty = bookmark("empty", &["note:: nothing to run"]);
+        let dep = bookmark("dep", &["echo dep \\", "{{ optional }}"]);
+        let single = bookmark("single", &["make"]);
+        let multi = bookmark("multi", &["make", "make test"]);
+        let composed = bookmark(
+            "composed",
+            &[
+                "bookmark::empty",
+                "cmd::done",
+                "bookmark::dep",
+                "cmd::blank",
+            ],
+        );
+        let mut state = state_with_config(Config {
+            bookmarks: vec![empty, dep, single.clone(), multi.clone(), composed.clone()],
+            cmds: HashMap::from([
+                ("done".to_string(), "echo done".to_string()),
+                ("blank".to_string(), " ".to_string()),
+            ]),
+            ..Default::default()
+        });
+
+        assert_eq!(state.gen_command(&single, "").unwrap(), "make");
+        assert_eq!(state.gen_command(&multi, "").unwrap(), "make && make test");
+        assert_eq!(
+            state.gen_command(&composed, "").unwrap(),
+            "echo done && echo dep"
+        );
+
+        state.exec = true;
+        assert_eq!(state.gen_command(&single, "").unwrap(), "make\n");
+        assert_eq!(
+            state.gen_command(&multi, "").unwrap(),
+            "make && make test\n"
+        );
+        assert_eq!(
+            state.gen_command(&composed, "").unwrap(),
+            "echo done && echo dep\n"
+        );
+
+        state.separator = " \\\n&& ".to_string();
+        state.template_cache.borrow_mut().clear();
+        assert_eq!(
+            state.gen_command(&composed, "").unwrap(),
+            "echo done \\\n&& echo dep\n"
+        );
+    }
+
+    #[test]
+    fn gen_command_limits_the_template_depth() {
+        let mut bookmarks: Vec<Bookmark> = (0..40)
+            .map(|i| bookmark(&format!("b{}", i), &[&format!("bookmark::b{}", i + 1)]))
+            .collect();
+        bookmarks.push(bookmark("b40", &["echo end"]));
+        let root = bookmarks[0].clone();
+        let inner = bookmarks[20].clone();
+        let mut state = state_with_config(Config {
+            bookmarks,
+            ..Default::default()
+        });
+
+        // Rendering an inner link first doesn't shorten the chain from the root.
+        assert_eq!(state.gen_command(&inner, "").unwrap(), "echo end");
+        assert_eq!(
+            state.gen_command(&root, "").unwrap_err(),
+            "Maximum template depth of 32 exceeded at bookmark 'b33'"
+        );
+
+        state.max_template_depth = 64;
+        assert_eq!(state.gen_command(&root, "").unwrap(), "echo end");
+    }
+
+    #[test]
+    fn gen_command_expands_cmds_that_reference_cmds() {
+        let dep = bookmark("dep", &["echo dep"]);
+        let mut root = bookmark("root", &["cmd::release", "echo {{ name }}"]);
+        root.vars = HashMap::from([("name".to_string(), "done".to_string())]);
+        let looped = bookmark("looped", &["cmd::ping"]);
+        let state = state_with_config(Config {
+            bookmarks: vec![dep, root.clone(), looped.clone()],
+            cmds: HashMap::from([
+                ("build".to_string(), "cargo build".to_string()),
+                (
+                    "release".to_string(),
+                    "cmd::build\nbookmark::dep\ncargo publish".to_string(),
+                ),
+                ("ping".to_string(), "cmd::pong".to_string()),
+                ("pong".to_string(), "cmd::ping".to_string()),
+            ]),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            state.gen_command(&root, "").unwrap(),
+            "cargo build && echo dep && cargo publish && echo done"
+        );
+        assert_eq!(
+            state.gen_command(&looped, "").unwrap_err(),
+            "Circular dependency detected for command 'ping'"
+        );
+    }
+
+    #[test]
+    fn gen_command_skips_notes() {
+        let root = bookmark(
+            "root",
+            &[
+                "note:: run from the repo root",
+                "make",
+                "note:: {{undefined}}",
+                "make test",
+            ],
+        );
+        let state = state_with_config(Config {
+            bookmarks: vec![root.clone()],
+            ..Default::default()
+        });
+
+        assert_eq!(state.gen_command(&root, "").unwrap(), "make && make test");
+    }
+
+    #[test]
+    fn gen_command_expands_named_commands() {
+        let root = bookmark("root", &["cmd::prepare", "echo ready", "cmd::finish"]);
+        let state = state_with_config(Config {
+            cmds: HashMap::from([
+                ("prepare".to_string(), "echo prepare".to_string()),
+                ("finish".to_string(), "echo finish".to_string()),
+            ]),
+            bookmarks: vec![root.clone()],
+            ..Default::default()
+        });
+
+        let cmd = state.gen_command(&root, "").unwrap();
+
+        assert_eq!(cmd, "echo prepare && echo ready && echo finish");
+    }
+
+    #[test]
+    fn gen_preview_omits_the_exec_newline() {
+        let mut root = bookmark("root", &["echo {{ name }}"]);
+        root.exec = Some(true);
+        root.vars = HashMap::from([("name".to_string(), "world".to_string())]);
+        let state = state_with_config(Config {
+            bookmarks: vec![root.clone()],
+            ..Default::default()
+        });
+
+        assert_eq!(state.gen_command(&root, "").unwrap(), "echo world\n");
+        assert_eq!(state.gen_preview(&root).unwrap(), "echo world");
+    }
+
+    #[test]
+    fn gen_command_wraps_commands_for_the_bookmark_or_default_shell() {
+        let mut fish = bookmark("fish", &["echo 'hi'", "ls"]);
+        fish.shell = Some("fish".to_string());
+        fish.separator = Some(" \\\n&& ".to_string());
+        let plain = bookmark("plain", &["ls"]);
+        let mut state = state_with_config(Config {
+            bookmarks: vec![fish.clone(), plain.clone()],
+            ..Default::default()
+        });
+
+        assert_eq!(
+            state.gen_command(&fish, "").unwrap(),
+            "fish -c 'echo '\\''hi'\\'' \\\n&& ls'"
+        );
+        assert_eq!(state.gen_command(&plain, "").unwrap(), "ls");
+
+        state.shell = Some("pwsh".to_string());
+        assert_eq!(state.gen_command(&plain, "").unwrap(), "pwsh -c 'ls'");
+    }
+
+    #[test]
+    fn gen_command_quotes_vars_when_enabled() {
+        let mut root = bookmark("root", &["echo {{ msg }} {{{ msg }}}"]);
+        root.vars = HashMap::from([("msg".to_string(), "it's here".to_string())]);
+        let mut state = state_with_config(Config {
+            bookmarks: vec![root.clone()],
+            ..Default::default()
+        });
+        state.quote_vars = true;
+
+        assert_eq!(
+            state.gen_command(&root, "").unwrap(),
+            "echo 'it'\\''s here' it's here"
+        );
+    }
+
+    #[test]
+    fn quote_vars_doesnt_quote_env_values_or_cwds_twice() {
+        let mut root = bookmark("root", &["make"]);
+        root.cwd = Some("{{ project }}".to_string());
+        root.env = HashMap::from([("STAGE".to_string(), "{{ stage }}".to_string())]);
+        root.vars = HashMap::from([
+            ("project".to_string(), "/srv".to_string()),
+            ("stage".to_string(), "it's prod".to_string()),
+        ]);
+        let mut state = state_with_config(Config {
+            bookmarks: vec![root.clone()],
+            ..Default::default()
+        });
+        state.separator = " && ".to_string();
+        state.quote_vars = true;
+
+        assert_eq!(
+            state.gen_command(&root, "").unwrap(),
+            "env STAGE='it'\\''s prod' sh -c 'cd '\\''/srv'\\'' && make'"
+        );
+    }
+
+    #[test]
+    fn gen_command_expands_nested_vars() {
+        let root = bookmark("root", &["cd {{ project }}"]);
+        let state = state_with_config(Config {
+            vars: HashMap::from([
+                ("base".to_string(), "/home/me".to_string()),
+                ("work".to_string(), "{{ base }}/work".to_string()),
+                ("project".to_string(), "{{ work }}/app".to_string()),
+            ]),
+            bookmarks: vec![root.clone()],
+            ..Default::default()
+        });
+
+        assert_eq!(
+            state.gen_command(&root, "").unwrap(),
+            "cd /home/me/work/app"
+        );
+    }
+
+    #[test]
+    fn gen_command_reports_cyclic_vars() {
+        let root = bookmark("root", &["echo {{ a }}"]);
+        let state = state_with_config(Config {
+            vars: HashMap::from([
+                ("a".to_string(), "x{{ b }}".to_string()),
+                ("b".to_string(), "y{{ a }}".to_string()),
+                ("c".to_string(), "plain".to_string()),
+            ]),
+            bookmarks: vec![root.clone()],
+            ..Default::default()
+        });
+
+        assert_eq!(
+            state.gen_command(&root, "").unwrap_err(),
+            "Vars reference each other in a cycle: a, b"
+        );
+    }
+
+    #[test]
+    fn gen_command_uses_separator_from_bookmark_or_global_config() {
+        let mut root = bookmark("root", &["echo one", "echo two"]);
+        root.separator = Some(" || ".to_string());
+
+        let state = State {
+            separator: " && ".to_string(),
+            config: Config {
+                bookmarks: vec![root.clone()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let cmd = state.gen_command(&root, "").unwrap();
+
+        assert_eq!(cmd, "echo one || echo two");
+    }
+
+    #[test]
+    fn gen_command_changes_to_the_rendered_cwd_first() {
+        let mut root = bookmark("root", &["make"]);
+        root.cwd = Some("{{project}}/app".to_string());
+        root.vars = HashMap::from([("project".to_string(), "/src/web".to_string())]);
+        let state = State {
+            separator: " && ".to_string(),
+            config: Config {
+                bookmarks: vec![root.clone()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            state.gen_command(&root, "").unwrap(),
+            "cd '/src/web/app' && make"
+        );
+
+        root.cwd = Some("~/my web".to_string());
+        assert_eq!(
+            state.gen_command(&root, "").unwrap(),
+            "cd ~/'my web' && make"
+        );
+    }
+
+    #[test]
+    fn pane_commands_apply_a_relative_cwd_once() {
+        let mut root = bookmark("root", &["make"]);
+        root.cwd = Some("my app".to_string());
+        root.exec = Some(true);
+        let state = State {
+            separator: " && ".to_string(),
+            ..state_with_config(Config {
+                bookmarks: vec![root.clone()],
+                ..Default::default()
+            })
+        };
+
+        let command = state.pane_command(&state.gen_command(&root, "").unwrap());
+
+        assert_eq!(command.cwd, Some(state.get_cwd()));
+        assert_eq!(command.args, vec!["-c", "cd 'my app' && make"]);
+    }
+
+    #[test]
+    fn gen_command_runs_the_commands_with_the_rendered_env() {
+        let mut root = bookmark("root", &["make", "make test"]);
+        root.env = HashMap::from([
+            ("STAGE".to_string(), "it's {{stage}}".to_string()),
+            ("DEBUG".to_string(), "1".to_string()),
+        ]);
+        root.vars = HashMap::from([("stage".to_string(), "prod".to_string())]);
+        let mut state = State {
+            separator: " && ".to_string(),
+            config: Config {
+                bookmarks: vec![root.clone()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            state.gen_command(&root, "").unwrap(),
+            "env DEBUG='1' STAGE='it'\\''s prod' sh -c 'make && make test'"
+        );
+
+        state.shell = Some("fish".to_string());
+        assert_eq!(
+            state.gen_command(&root, "").unwrap(),
+            "env DEBUG='1' STAGE='it'\\''s prod' fish -c 'make && make test'"
+        );
+    }
+
+    #[test]
+    fn commands_dump_reports_errors_per_bookmark() {
+        let state = State {
+            separator: " && ".to_string(),
+            ..state_with_config(Config {
+                bookmarks: vec![
+                    bookmark("build", &["make", "make test"]),
+                    bookmark("broken", &["bookmark::missing"]),
+                ],
+                ..Default::default()
+            })
+        };
+
+        let (dump, errors) = state.commands_dump();
+        let lines: Vec<serde_json::Value> = dump
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(errors, 1);
+        assert_eq!(lines[0]["name"], "build");
+        assert_eq!(lines[0]["command"], "make && make test");
+        assert_eq!(lines[1]["name"], "broken");
+        assert_eq!(lines[1]["error"], "Bookmark 'missing' not found");
+    }
+
+    #[test]
+    fn gen_command_reuses_rendered_commands_until_the_cache_is_cleared() {
+        let root = bookmark("root", &["bookmark::dep", "echo {{name}}"]);
+        let mut state = State {
+            separator: " && ".to_string(),
+            ..state_with_config(Config {
+                bookmarks: vec![root.clone(), bookmark("dep", &["echo dep"])],
+                vars: HashMap::from([("name".to_string(), "one".to_string())]),
+                ..Default::default()
+            })
+        };
+        assert_eq!(
+            state.gen_command(&root, "").unwrap(),
+            "echo dep && echo one"
+        );
+        assert_eq!(state.template_cache.borrow().len(), 1);
+
+        // Served from the cache, so the changed var is not rendered yet.
+        state
+            .config
+            .vars
+            .insert("name".to_string(), "two".to_string());
+        assert_eq!(
+            state.gen_command(&root, "").unwrap(),
+            "echo dep && echo one"
+        );
+
+        state.template_cache.borrow_mut().clear();
+        assert_eq!(
+            state.gen_command(&root, "").unwrap(),
+            "echo dep && echo two"
+        );
+    }
+
+    #[test]
+    fn gen_command_uses_global_separator_when_bookmark_override_is_missing() {
+        let root = bookmark("root", &["echo one", "echo two"]);
+        let state = State {
+            separator: " ~~ ".to_string(),
+            config: Config {
+                bookmarks: vec![root.clone()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let cmd = state.gen_command(&root, "").unwrap();
+
+        assert_eq!(cmd, "echo one ~~ echo two");
+    }
+
+    #[test]
+    fn gen_command_supports_mixed_bookmark_and_cmd_expansion() {
+        let dependency = bookmark("dep", &["cmd::prepare", "echo dep"]);
+        let root = bookmark("root", &["echo start", "bookmark::dep", "cmd::finish"]);
+        let state = state_with_config(Config {
+            cmds: HashMap::from([
+                ("prepare".to_string(), "echo prepare".to_string()),
+                ("finish".to_string(), "echo finish".to_string()),
+            ]),
+            bookmarks: vec![dependency, root.clone()],
+            ..Default::default()
+        });
+
+        let cmd = state.gen_command(&root, "").unwrap();
+
+        assert_eq!(cmd, "echo start && echo prepare && echo dep && echo finish");
+    }
+
+    #[test]
+    fn gen_command_appends_args_inside_the_shell_wrapper() {
+        let mut root = bookmark("root", &["cargo test"]);
+        root.exec = Some(true);
+        let state = state_with_config(Config::default());
+        assert_eq!(
+            state.gen_command(&root, " -- --nocapture ").unwrap(),
+            "cargo test -- --nocapture\n"
+        );
+
+        root.shell = Some("fish".to_string());
+        assert_eq!(
+            state.gen_command(&root, "--release").unwrap(),
+            "fish -c 'cargo test --release'\n"
+        );
+    }
+
+    #[test]
+    fn gen_command_appends_newline_when_exec_is_enabled() {
+        let root = bookmark("root", &["echo run"]);
+        let state = State {
+            exec: true,
+            config: Config {
+                bookmarks: vec![root.clone()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let cmd = state.gen_command(&root, "").unwrap();
+
+        assert_eq!(cmd, "echo run\n");
+    }
+
+    #[test]
+    fn gen_command_never_appends_newline_in_dry_run() {
+        let mut root = bookmark("root", &["rm -rf build"]);
+        root.exec = Some(true);
+        let state = State {
+            exec: true,
+            dry_run: true,
+            ..Default::default()
+        };
+
+        assert_eq!(state.gen_command(&root, "").unwrap(), "rm -rf build");
+    }
+
+    #[test]
+    fn gen_workflow_steps_renders_each_bookmark_separately() {
+        let build = bookmark("build", &["cargo build"]);
+        let test = bookmark("test", &["cargo test", "echo done"]);
+        let state = state_with_config(Config {
+            bookmarks: vec![build, test],
+            ..Default::default()
+        });
+        let workflow = Workflow::new(1, "ci".to_string(), vec!["build".into(), "test".into()]);
+
+        let steps = state.gen_workflow_steps(&workflow).unwrap();
+
+        assert_eq!(
+            steps,
+            vec![
+                WorkflowStep {
+                    bookmark: "build".to_string(),
+                    cmd: "cargo build".to_string(),
+                },
+                WorkflowStep {
+                    bookmark: "test".to_string(),
+                    cmd: "cargo test && echo done".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_reports_every_broken_bookmark_and_workflow() {
+        let mut prompted = bookmark("prompted", &["echo {{ who }}"]);
+        prompted.prompt_vars = vec!["who".to_string()];
+        let state = state_with_config(Config {
+            required_vars: vec!["who".to_string()],
+            workflows: HashMap::from([("ci".to_string(), vec!["gone".to_string()])]),
+            bookmarks: vec![
+                bookmark("ok", &["echo ok"]),
+                bookmark("unknown_cmd", &["cmd::missing"]),
+                bookmark("loop", &["bookmark::loop"]),
+                prompted,
+            ],
+            ..Default::default()
+        });
 
-                    self.set_filter();
+        assert_eq!(
+            state.validate(),
+            vec![
+                "Bookmark 'unknown_cmd': Command key 'missing' not found in cmds".to_string(),
+                "Bookmark 'loop': Circular dependency detected for bookmark 'loop'".to_string(),
+                "Workflow 'ci': Bookmark 'gone' not found".to_string(),
+            ]
+        );
+    }
 
-                    should_render = true;
-                }
-                Mode::Edit => match self.editable_files.get_selected() {
-                    Some(file) => {
-                        let file = FileToOpen::new(file.path.as_str()).with_cwd(self.get_cwd());
-                        open_file_in_place(file, Default::default());
-                    }
-                    None => should_render = true,
-                },
-                _ => {}
-            },
-            _ => {
-                // Configurable keys
-                if self.keybindings.edit.matches(&key) {
-                    self.mode = Mode::Edit;
-                    self.filter = String::new();
-                    self.filter_mode = FilterMode::Name;
-                    self.view_desc = false;
-                    self.reset_selection();
-                    self.set_filter();
-                    should_render = true;
-                } else if self.keybindings.reload.matches(&key) {
-                    if let Err(e) = self.load_config() {
-                        self.error_mgr.handle_error(format!(
-                            "Failed to load config file '{}': {}.",
-                            self.get_path().display(),
-                            e
-                        ));
-                    }
+    #[test]
+    fn validate_lists_every_dangling_reference_of_a_bookmark() {
+        let state = state_with_config(Config {
+            bookmarks: vec![bookmark(
+                "typos",
+                &["cmd::buidl", "echo ok", "bookmark::tset", "cmd::lint"],
+            )],
+            ..Default::default()
+        });
 
-                    self.filter = "".to_string();
+        assert_eq!(
+            state.validate(),
+            vec![
+                "Bookmark 'typos': Command key 'buidl' not found in cmds".to_string(),
+                "Bookmark 'typos': Bookmark 'tset' not found".to_string(),
+                "Bookmark 'typos': Command key 'lint' not found in cmds".to_string(),
+            ]
+        );
+    }
 
-                    self.reset_selection();
+    #[test]
+    fn shortcut_keys_run_bookmarks_whatever_the_filter() {
+        let mut deploy = bookmark("deploy", &["make deploy"]);
+        deploy.key = Some('d');
+        let mut state = state_with_config(Config {
+            bookmarks: vec![bookmark("build", &["make"]), deploy],
+            ..Default::default()
+        });
 
-                    self.mode = Mode::default();
+        assert_eq!(
+            state.shortcut_bookmark('d').map(|b| b.name),
+            Some("deploy".to_string())
+        );
+        assert!(state.shortcut_bookmark('b').is_none());
 
-                    should_render = true;
-                } else if self.keybindings.switch_filter_label.matches(&key) {
-                    if self.mode == Mode::Bookmarks {
-                        self.filter_mode = self.filter_mode.switch_to(FilterMode::Label);
-                        self.set_filter();
-                        should_render = true;
-                    }
-                } else if self.keybindings.switch_filter_id.matches(&key) {
-                    match self.mode {
-                        Mode::Bookmarks | Mode::Labels | Mode::Edit => {
-                            self.filter_mode = self.filter_mode.switch_to(FilterMode::ID);
-                            self.set_filter();
-                            should_render = true;
-                        }
-                        _ => {}
-                    }
-                } else if self.keybindings.describe.matches(&key) {
-                    #[allow(clippy::collapsible_if)]
-                    if self.mode == Mode::Bookmarks {
-                        self.view_desc = !self.view_desc;
-                        should_render = true;
-                    }
-                }
-            }
-        }
+        state.filter = "b".to_string();
+        state.filter_mode = FilterMode::ID;
+        assert_eq!(
+            state.shortcut_bookmark('d').map(|b| b.name),
+            Some("deploy".to_string())
+        );
 
-        should_render
+        state.mode = Mode::Labels;
+        assert!(state.shortcut_bookmark('d').is_none());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::State;
-    use crate::bookmark::Bookmark;
-    use crate::config::Config;
-    use std::collections::HashMap;
+    #[test]
+    fn gen_workflow_steps_rejects_unknown_bookmarks() {
+        let state = state_with_config(Config::default());
+        let workflow = Workflow::new(1, "ci".to_string(), vec!["missing".into()]);
 
-    fn bookmark(name: &str, cmds: &[&str]) -> Bookmark {
-        Bookmark {
-            name: name.to_string(),
-            cmds: cmds.iter().map(|cmd| cmd.to_string()).collect(),
-            ..Default::default()
-        }
+        let err = state.gen_workflow_steps(&workflow).unwrap_err();
+
+        assert_eq!(err, "Bookmark 'missing' not found");
     }
 
-    fn state_with_config(config: Config) -> State {
-        State {
-            config,
+    #[test]
+    fn gen_command_fills_var_defaults_when_vars_are_missing() {
+        let mut root = bookmark("root", &["git push {{ remote }} {{ branch }}"]);
+        root.vars = HashMap::from([("branch".to_string(), "feature".to_string())]);
+        let state = state_with_config(Config {
+            var_defaults: HashMap::from([
+                ("remote".to_string(), "origin".to_string()),
+                ("branch".to_string(), "main".to_string()),
+            ]),
+            bookmarks: vec![root.clone()],
             ..Default::default()
-        }
+        });
+
+        let cmd = state.gen_command(&root, "").unwrap();
+
+        assert_eq!(cmd, "git push origin feature");
     }
 
     #[test]
-    fn gen_command_expands_nested_bookmarks() {
-        let dependency = bookmark("dep", &["echo dep-1", "echo dep-2"]);
-        let root = bookmark("root", &["echo start", "bookmark::dep", "echo end"]);
+    fn gen_command_rejects_unresolved_required_vars() {
+        let root = bookmark(
+            "root",
+            &["echo {{ optional }}", "git checkout {{ branch }}"],
+        );
         let state = state_with_config(Config {
-            bookmarks: vec![dependency, root.clone()],
+            required_vars: vec!["branch".to_string()],
+            bookmarks: vec![root.clone()],
             ..Default::default()
         });
 
-        let cmd = state.gen_command(&root).unwrap();
+        let err = state.gen_command(&root, "").unwrap_err();
 
         assert_eq!(
-            cmd,
-            "echo start \\\n&& echo dep-1 \\\n&& echo dep-2 \\\n&& echo end"
+            err,
+            "Required variable 'branch' is not set for bookmark 'root'"
         );
     }
 
     #[test]
-    fn gen_command_expands_named_commands() {
-        let root = bookmark("root", &["cmd::prepare", "echo ready", "cmd::finish"]);
+    fn gen_command_renders_missing_optional_vars_as_empty() {
+        let root = bookmark(
+            "root",
+            &[
+                "echo {{ missing }}done",
+                "{{#if flag}}echo flag{{/if}}echo end",
+            ],
+        );
         let state = state_with_config(Config {
-            cmds: HashMap::from([
-                ("prepare".to_string(), "echo prepare".to_string()),
-                ("finish".to_string(), "echo finish".to_string()),
-            ]),
             bookmarks: vec![root.clone()],
             ..Default::default()
         });
 
-        let cmd = state.gen_command(&root).unwrap();
+        let cmd = state.gen_command(&root, "").unwrap();
 
-        assert_eq!(cmd, "echo prepare \\\n&& echo ready \\\n&& echo finish");
+        assert_eq!(cmd, "echo done && echo end");
     }
 
     #[test]
-    fn gen_command_uses_separator_from_bookmark_or_global_config() {
-        let mut root = bookmark("root", &["echo one", "echo two"]);
-        root.separator = Some(" || ".to_string());
-
+    fn gen_command_expands_env_helper() {
+        let root = bookmark("root", &["cd {{env \"HOME\"}}"]);
         let state = State {
-            separator: " && ".to_string(),
+            env: HashMap::from([("HOME".to_string(), "/home/user".to_string())]),
             config: Config {
                 bookmarks: vec![root.clone()],
                 ..Default::default()
@@ -405,63 +2884,405 @@ mod tests {
             ..Default::default()
         };
 
-        let cmd = state.gen_command(&root).unwrap();
-
-        assert_eq!(cmd, "echo one || echo two");
+        assert_eq!(state.gen_command(&root, "").unwrap(), "cd /home/user");
+        assert!(state
+            .gen_command(&bookmark("missing", &["echo {{env \"NOPE\"}}"]), "")
+            .unwrap_err()
+            .contains("Environment variable 'NOPE' is not set"));
     }
 
     #[test]
-    fn gen_command_uses_global_separator_when_bookmark_override_is_missing() {
-        let root = bookmark("root", &["echo one", "echo two"]);
-        let state = State {
-            separator: " ~~ ".to_string(),
-            config: Config {
-                bookmarks: vec![root.clone()],
-                ..Default::default()
-            },
+    fn restore_session_drops_stale_filter_and_clamps_selection() {
+        let mut state = State {
+            bookmarks: FilteredList::new(vec![
+                bookmark("build", &["make"]),
+                bookmark("test", &["make test"]),
+            ]),
+            ignore_case: false,
+            fuzzy_search: false,
             ..Default::default()
         };
 
-        let cmd = state.gen_command(&root).unwrap();
+        state.restore_session(Session {
+            mode: Mode::Bookmarks as u32,
+            filter: "removed".to_string(),
+            filter_mode: FilterMode::Name,
+            position: 5,
+        });
 
-        assert_eq!(cmd, "echo one ~~ echo two");
+        assert!(state.filter.is_empty());
+        assert_eq!(state.bookmarks.len(), 2);
+        assert_eq!(state.bookmarks.get_position(), 1);
     }
 
     #[test]
-    fn gen_command_supports_mixed_bookmark_and_cmd_expansion() {
-        let dependency = bookmark("dep", &["cmd::prepare", "echo dep"]);
-        let root = bookmark("root", &["echo start", "bookmark::dep", "cmd::finish"]);
-        let state = state_with_config(Config {
-            cmds: HashMap::from([
-                ("prepare".to_string(), "echo prepare".to_string()),
-                ("finish".to_string(), "echo finish".to_string()),
+    fn restore_session_falls_back_to_name_for_unsupported_filter_modes() {
+        let mut state = State {
+            labels: FilteredList::new(vec![
+                Label::new(1, "docker".to_string()),
+                Label::new(2, "git".to_string()),
             ]),
-            bookmarks: vec![dependency, root.clone()],
             ..Default::default()
+        };
+
+        state.restore_session(Session {
+            mode: Mode::Labels as u32,
+            filter: "git".to_string(),
+            filter_mode: FilterMode::Label,
+            position: 0,
         });
 
-        let cmd = state.gen_command(&root).unwrap();
+        assert_eq!(state.mode, Mode::Labels);
+        assert_eq!(state.filter_mode, FilterMode::Name);
+        assert_eq!(state.labels.get_selected().unwrap().name, "git");
+    }
 
-        assert_eq!(
-            cmd,
-            "echo start \\\n&& echo prepare \\\n&& echo dep \\\n&& echo finish"
-        );
+    #[test]
+    fn set_initial_view_sets_mode_and_filter() {
+        let mut build = bookmark("build", &["make"]);
+        build.labels = vec!["git".to_string()];
+        let mut state = State {
+            bookmarks: FilteredList::new(vec![build, bookmark("test", &["make test"])]),
+            mode: Mode::Labels,
+            ..Default::default()
+        };
+
+        state
+            .set_initial_view(Mode::Bookmarks, "git".to_string(), FilterMode::Label)
+            .unwrap();
+
+        assert_eq!(state.mode, Mode::Bookmarks);
+        assert_eq!(state.bookmarks.len(), 1);
+        assert_eq!(state.bookmarks.get_selected().unwrap().name, "build");
+
+        let err = state
+            .set_initial_view(Mode::Labels, String::new(), FilterMode::Label)
+            .unwrap_err();
+        assert_eq!(err, "Label filter mode is not supported in Labels mode");
+        assert_eq!(state.mode, Mode::Labels);
+        assert_eq!(state.filter_mode, FilterMode::Name);
     }
 
     #[test]
-    fn gen_command_appends_newline_when_exec_is_enabled() {
-        let root = bookmark("root", &["echo run"]);
-        let state = State {
+    fn select_named_falls_back_to_the_position() {
+        let mut state = State {
+            bookmarks: FilteredList::new(vec![
+                bookmark("build", &["make"]),
+                bookmark("lint", &["make lint"]),
+                bookmark("test", &["make test"]),
+            ]),
+            ..Default::default()
+        };
+
+        state.select_named("test", 0);
+        assert_eq!(state.bookmarks.get_position(), 2);
+
+        state.select_named("removed", 1);
+        assert_eq!(state.bookmarks.get_selected().unwrap().name, "lint");
+    }
+
+    #[test]
+    fn go_back_restores_the_previous_view() {
+        let mut state = State {
+            bookmarks: FilteredList::new(vec![
+                bookmark("build", &["make"]),
+                bookmark("test", &["make test"]),
+            ]),
+            ignore_case: false,
+            fuzzy_search: false,
+            ..Default::default()
+        };
+        state.filter = "te".to_string();
+        state.set_filter();
+
+        state.push_view();
+        state.mode = Mode::Labels;
+        state.filter.clear();
+        state.filter_mode = FilterMode::Label;
+
+        assert!(state.go_back());
+        assert_eq!(state.mode, Mode::Bookmarks);
+        assert_eq!(state.filter, "te");
+        assert_eq!(state.filter_mode, FilterMode::Name);
+        assert_eq!(state.bookmarks.get_selected().unwrap().name, "test");
+        assert!(!state.go_back());
+    }
+
+    #[test]
+    fn switch_mode_preserves_the_filter_only_when_configured() {
+        let mut state = State {
+            labels: FilteredList::new(vec![Label::new(1, "docker".to_string())]),
+            filter: "dock".to_string(),
+            filter_mode: FilterMode::ID,
+            ..Default::default()
+        };
+        state.switch_mode(Mode::Labels);
+        assert_eq!(state.filter, "dock");
+        assert_eq!(state.filter_mode, FilterMode::Name);
+
+        state.preserve_filter_on_mode_switch = true;
+        state.filter_mode = FilterMode::ID;
+        state.filter = "1".to_string();
+        state.switch_mode(Mode::Edit);
+        assert_eq!(state.filter, "1");
+        assert_eq!(state.filter_mode, FilterMode::ID);
+
+        state.switch_mode(Mode::Usage);
+        assert!(state.filter.is_empty());
+        assert_eq!(state.filter_mode, FilterMode::Name);
+    }
+
+    #[test]
+    fn plugin_pane_size_finds_the_plugin_pane() {
+        let pane = |id, is_plugin, rows| PaneInfo {
+            id,
+            is_plugin,
+            pane_content_rows: rows,
+            pane_content_columns: 80,
+            ..Default::default()
+        };
+        let manifest = PaneManifest {
+            panes: HashMap::from([(0, vec![pane(3, false, 10), pane(3, true, 20)])]),
+        };
+        assert_eq!(plugin_pane_size(&manifest, 3), Some((20, 80)));
+        assert_eq!(plugin_pane_size(&manifest, 4), None);
+    }
+
+    #[test]
+    fn gen_marked_command_joins_the_marked_bookmarks_in_marking_order() {
+        let mut prompting = bookmark("deploy", &["deploy {{env}}"]);
+        prompting.prompt_vars = vec!["env".to_string()];
+        let mut state = State {
+            separator: " && ".to_string(),
             exec: true,
-            config: Config {
-                bookmarks: vec![root.clone()],
+            ..state_with_config(Config {
+                bookmarks: vec![
+                    bookmark("build", &["make"]),
+                    bookmark("test", &["make test"]),
+                    prompting,
+                ],
                 ..Default::default()
-            },
+            })
+        };
+        state.toggle_marked("test");
+        state.toggle_marked("build");
+        assert_eq!(state.gen_marked_command().unwrap(), "make test && make\n");
+
+        state.toggle_marked("test");
+        assert_eq!(state.marked, vec!["build".to_string()]);
+
+        state.toggle_marked("deploy");
+        assert!(state.gen_marked_command().is_err());
+    }
+
+    #[test]
+    fn piped_bookmarks_leave_the_focused_pane_alone() {
+        let floating = Bookmark {
+            run_in: Some(RunTarget::Floating),
+            ..bookmark("floating", &["top"])
+        };
+        let confirm = Bookmark {
+            confirm: true,
+            ..bookmark("confirm", &["rm -rf build"])
+        };
+        let mut prompting = bookmark("deploy", &["deploy {{env}}"]);
+        prompting.prompt_vars = vec!["env".to_string()];
+        let state = state_with_config(Config {
+            bookmarks: vec![bookmark("build", &["make"]), floating, confirm, prompting],
+            ..Default::default()
+        });
+
+        let target = |name: &str| state.piped_bookmark(name).map(|(_, target)| target);
+        assert_eq!(target("build"), Ok(RunTarget::NewPane));
+        assert_eq!(target("floating"), Ok(RunTarget::Floating));
+        assert!(target("confirm").is_err());
+        assert!(target("deploy").is_err());
+        assert!(target("missing").is_err());
+    }
+
+    #[test]
+    fn piped_filters_filter_by_name() {
+        let mut state = State {
+            bookmarks: FilteredList::new(vec![bookmark("build", &["make"])]),
+            filter: "1".to_string(),
+            filter_mode: FilterMode::ID,
             ..Default::default()
         };
 
-        let cmd = state.gen_command(&root).unwrap();
+        state.set_piped_filter("bui".to_string());
 
-        assert_eq!(cmd, "echo run\n");
+        assert_eq!(state.filter, "bui");
+        assert_eq!(state.filter_mode, FilterMode::Name);
+        assert_eq!(state.bookmarks.len(), 1);
+    }
+
+    #[test]
+    fn group_view_lists_the_bookmarks_by_group() {
+        let grouped = |name: &str, group: Option<&str>| Bookmark {
+            group: group.map(str::to_string),
+            ..bookmark(name, &["make"])
+        };
+        let mut state = State {
+            bookmarks: FilteredList::new(vec![
+                grouped("lint", None),
+                grouped("deploy", Some("ops")),
+                grouped("build", Some("dev")),
+                grouped("test", Some("dev")),
+            ]),
+            group_view: true,
+            ..Default::default()
+        };
+        state.set_filter();
+
+        let names: Vec<String> = state
+            .bookmarks
+            .iter()
+            .map(|(_, item)| item.value.name.clone())
+            .collect();
+        assert_eq!(names, vec!["build", "test", "deploy", "lint"]);
+    }
+
+    #[test]
+    fn filter_changed_selects_the_top_match() {
+        let mut state = State {
+            bookmarks: FilteredList::new(vec![
+                bookmark("build", &["make"]),
+                bookmark("test", &["make test"]),
+                bookmark("test all", &["make test-all"]),
+            ]),
+            fuzzy_search: false,
+            ..Default::default()
+        };
+        state.bookmarks.select(2);
+
+        state.filter = "test".to_string();
+        state.filter_changed();
+        assert_eq!(state.bookmarks.len(), 2);
+        assert_eq!(state.bookmarks.get_selected().unwrap().name, "test");
+
+        state.bookmarks.select(1);
+        state.filter.clear();
+        state.set_filter();
+        assert_eq!(state.bookmarks.len(), 3);
+        assert_eq!(state.bookmarks.get_selected().unwrap().name, "test all");
+    }
+
+    #[test]
+    fn id_filter_accepts_a_typed_range() {
+        let mut state = State {
+            bookmarks: FilteredList::new(
+                (1..=30)
+                    .map(|id| Bookmark {
+                        id,
+                        ..bookmark(&id.to_string(), &["true"])
+                    })
+                    .collect(),
+            ),
+            filter_mode: FilterMode::ID,
+            ..Default::default()
+        };
+        state.set_filter();
+
+        // Mirrors the ID branch of the `BareKey::Char` handler.
+        for c in "-10--2a0".chars() {
+            if state.accepts_id_char(c) {
+                state.filter.push(c);
+                state.filter_changed();
+            }
+        }
+        assert_eq!(state.filter, "10-20");
+        assert_eq!(state.bookmarks.len(), 11);
+    }
+
+    #[test]
+    fn clear_filter_resets_the_filter_and_selection() {
+        let mut state = State {
+            bookmarks: FilteredList::new(vec![
+                bookmark("build", &["make"]),
+                bookmark("test", &["make test"]),
+            ]),
+            filter: "te".to_string(),
+            filter_mode: FilterMode::Label,
+            ..Default::default()
+        };
+        state.set_filter();
+        assert_eq!(state.bookmarks.len(), 0);
+
+        state.clear_filter();
+        assert!(state.filter.is_empty());
+        assert_eq!(state.filter_mode, FilterMode::Name);
+        assert_eq!(state.bookmarks.len(), 2);
+        assert_eq!(state.bookmarks.get_position(), 0);
+    }
+
+    #[test]
+    fn label_completion_applies_only_to_the_label_filter() {
+        let mut tagged = bookmark("build", &["make"]);
+        tagged.labels = vec!["docker".to_string(), "dev".to_string()];
+        let mut state = State {
+            filter: "doc".to_string(),
+            ..state_with_config(Config {
+                bookmarks: vec![tagged],
+                ..Default::default()
+            })
+        };
+        assert_eq!(state.label_completion(), None);
+
+        state.filter_mode = FilterMode::Label;
+        assert_eq!(state.label_completion(), Some("docker".to_string()));
+
+        state.mode = Mode::Labels;
+        assert_eq!(state.label_completion(), None);
+    }
+
+    #[test]
+    fn label_suggestions_are_walked_and_filled_in() {
+        let mut tagged = bookmark("build", &["make"]);
+        tagged.labels = vec!["dev".to_string(), "kube-dev".to_string()];
+        let mut state = State {
+            filter: "prod, de".to_string(),
+            filter_mode: FilterMode::Label,
+            ..state_with_config(Config {
+                bookmarks: vec![tagged],
+                ..Default::default()
+            })
+        };
+        assert_eq!(state.label_suggestions(), vec!["dev", "kube-dev"]);
+
+        state.select_suggestion(true);
+        assert_eq!(state.suggestion, Some(1));
+        state.select_suggestion(false);
+        assert_eq!(state.suggestion, Some(0));
+        state.select_suggestion(false);
+        state.fill_suggestion();
+        assert_eq!(state.filter, "prod, kube-dev");
+        assert_eq!(state.suggestion, None);
+
+        state.mode = Mode::Labels;
+        assert!(state.label_suggestions().is_empty());
+    }
+
+    #[test]
+    fn vim_keys_navigate_only_while_the_filter_is_empty() {
+        let mut state = State {
+            vim_keys: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            state.vim_key('j'),
+            Some(KeyWithModifier::new(BareKey::Down))
+        );
+        assert_eq!(
+            state.vim_key('l'),
+            Some(KeyWithModifier::new(BareKey::Right))
+        );
+        assert_eq!(state.vim_key('x'), None);
+
+        state.vim_search = true;
+        assert_eq!(state.vim_key('j'), None);
+
+        state.vim_search = false;
+        state.filter = "b".to_string();
+        assert_eq!(state.vim_key('k'), None);
     }
 }
@@ -0,0 +1,57 @@
+use crate::core::{DescGetter, IdGetter, LabelsGetter, NameGetter};
+use std::collections::VecDeque;
+
+#[derive(Default, Debug, Clone)]
+pub(crate) struct Workflow {
+    pub id: usize,
+    pub name: String,
+    pub steps: Vec<String>,
+}
+
+impl Workflow {
+    pub(crate) fn new(id: usize, name: String, steps: Vec<String>) -> Self {
+        Self { id, name, steps }
+    }
+
+    pub(crate) fn summary(&self) -> String {
+        format!("{} ({})", self.name, self.steps.join(" -> "))
+    }
+}
+
+impl NameGetter for Workflow {
+    fn get_name(&self) -> String {
+        self.name.to_string()
+    }
+}
+
+impl IdGetter for Workflow {
+    fn get_id(&self) -> usize {
+        self.id
+    }
+}
+
+impl LabelsGetter for Workflow {
+    fn get_labels(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl DescGetter for Workflow {
+    fn get_desc(&self) -> String {
+        String::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WorkflowStep {
+    pub bookmark: String,
+    pub cmd: String,
+}
+
+// A workflow that is being executed: the next step is only started
+// once the command pane of the previous one has exited successfully.
+#[derive(Debug, Clone)]
+pub(crate) struct WorkflowRun {
+    pub name: String,
+    pub steps: VecDeque<WorkflowStep>,
+}